@@ -30,20 +30,41 @@ export interface Cluster {
   items: Set<string>;
 }
 
+export interface NodeAttributes {
+  resource_type?: string;
+  package?: string;
+  fqn?: string[];
+  tags?: string[];
+  materialization?: string;
+  owner?: string;
+  extra?: {
+    [key: string]: string;
+  };
+}
+
 export interface PowerNodeObject {
   id: string;
   cluster: Cluster;
+  test_count?: number;
+  projects?: string[];
+  attributes?: NodeAttributes;
+  parent?: string;
+  children?: string[];
+  depth: number;
 }
 
 export interface PowerEdgeObject {
   from: string;
   to: string;
+  residual?: boolean;
+  negative_edges?: { from: string; to: string }[];
 }
 
 export interface JsonPowerGraph {
   power_nodes: PowerNodeObject[];
   power_edges: PowerEdgeObject[];
   clusters: Cluster[];
+  directed?: boolean;
 }
 
 export async function getManifest(path: string): Promise<Manifest> {
@@ -0,0 +1,246 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+/// Typed attributes of a node, such as its dbt resource type and owner, with
+/// any other attributes kept as string key/value pairs.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct NodeAttributes {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    resource_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    package: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    fqn: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    materialization: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    owner: Option<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    extra: BTreeMap<String, String>,
+}
+
+#[wasm_bindgen]
+impl NodeAttributes {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> NodeAttributes {
+        NodeAttributes::default()
+    }
+
+    /// Set an attribute by name. `resource_type`, `package`, `materialization`
+    /// and `owner` set the typed attributes, `tags` is split on commas, `fqn`
+    /// is split on dots, and anything else is kept as a key/value pair.
+    #[wasm_bindgen]
+    pub fn set(&mut self, key: &str, value: &str) {
+        match key {
+            "resource_type" => self.resource_type = Some(value.to_string()),
+            "package" | "package_name" => self.package = Some(value.to_string()),
+            "materialization" | "materialized" => self.materialization = Some(value.to_string()),
+            "owner" => self.owner = Some(value.to_string()),
+            "tags" => {
+                self.tags = value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|tag| !tag.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            }
+            "fqn" => self.fqn = value.split('.').map(str::to_string).collect(),
+            _ => {
+                self.extra.insert(key.to_string(), value.to_string());
+            }
+        }
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn resource_type(&self) -> Option<String> {
+        self.resource_type.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn package(&self) -> Option<String> {
+        self.package.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn fqn(&self) -> Vec<String> {
+        self.fqn.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn tags(&self) -> Vec<String> {
+        self.tags.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn materialization(&self) -> Option<String> {
+        self.materialization.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn owner(&self) -> Option<String> {
+        self.owner.clone()
+    }
+
+    /// The value of an attribute without a typed getter.
+    #[wasm_bindgen]
+    pub fn get(&self, key: &str) -> Option<String> {
+        self.extra.get(key).cloned()
+    }
+
+    /// The names of the attributes without a typed getter.
+    #[wasm_bindgen]
+    pub fn keys(&self) -> Vec<String> {
+        self.extra.keys().cloned().collect()
+    }
+
+    #[wasm_bindgen]
+    pub fn is_empty(&self) -> bool {
+        *self == NodeAttributes::default()
+    }
+}
+
+impl NodeAttributes {
+    pub fn with_resource_type(mut self, resource_type: impl Into<String>) -> NodeAttributes {
+        self.resource_type = Some(resource_type.into());
+        self
+    }
+
+    pub fn with_package(mut self, package: impl Into<String>) -> NodeAttributes {
+        self.package = Some(package.into());
+        self
+    }
+
+    pub fn with_fqn(mut self, fqn: Vec<String>) -> NodeAttributes {
+        self.fqn = fqn;
+        self
+    }
+
+    pub fn with_tags(mut self, tags: Vec<String>) -> NodeAttributes {
+        self.tags = tags;
+        self
+    }
+
+    pub fn with_materialization(mut self, materialization: impl Into<String>) -> NodeAttributes {
+        self.materialization = Some(materialization.into());
+        self
+    }
+
+    pub fn with_owner(mut self, owner: impl Into<String>) -> NodeAttributes {
+        self.owner = Some(owner.into());
+        self
+    }
+
+    /// Add an attribute without a typed getter.
+    pub fn with(mut self, key: impl Into<String>, value: impl Into<String>) -> NodeAttributes {
+        self.extra.insert(key.into(), value.into());
+        self
+    }
+
+    /// Build attributes from key/value pairs, as with `set`.
+    pub fn from_pairs<K: AsRef<str>, V: AsRef<str>>(
+        pairs: impl IntoIterator<Item = (K, V)>,
+    ) -> NodeAttributes {
+        let mut attributes = NodeAttributes::default();
+        for (key, value) in pairs {
+            attributes.set(key.as_ref(), value.as_ref());
+        }
+        attributes
+    }
+
+    pub fn extra(&self) -> &BTreeMap<String, String> {
+        &self.extra
+    }
+
+    /// The attributes shared by every one of `attributes`: typed attributes
+    /// and key/value pairs with the same value everywhere, tags present
+    /// everywhere, and the longest common fqn prefix.
+    pub fn shared<'a>(attributes: impl IntoIterator<Item = &'a NodeAttributes>) -> NodeAttributes {
+        let mut attributes = attributes.into_iter();
+        let Some(first) = attributes.next() else {
+            return NodeAttributes::default();
+        };
+
+        let mut shared = first.clone();
+        for other in attributes {
+            let keep = |value: &mut Option<String>, other: &Option<String>| {
+                if value != other {
+                    *value = None;
+                }
+            };
+            keep(&mut shared.resource_type, &other.resource_type);
+            keep(&mut shared.package, &other.package);
+            keep(&mut shared.materialization, &other.materialization);
+            keep(&mut shared.owner, &other.owner);
+
+            let common = shared
+                .fqn
+                .iter()
+                .zip(other.fqn.iter())
+                .take_while(|(a, b)| a == b)
+                .count();
+            shared.fqn.truncate(common);
+            shared.tags.retain(|tag| other.tags.contains(tag));
+            shared
+                .extra
+                .retain(|key, value| other.extra.get(key) == Some(value));
+        }
+
+        shared
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_attributes() {
+        let attributes = NodeAttributes::from_pairs([
+            ("resource_type", "model"),
+            ("materialized", "table"),
+            ("tags", "finance, daily"),
+            ("fqn", "jaffle.staging.orders"),
+            ("label", "Orders"),
+        ]);
+
+        assert_eq!(attributes.resource_type(), Some("model".to_string()));
+        assert_eq!(attributes.materialization(), Some("table".to_string()));
+        assert_eq!(attributes.tags(), vec!["finance", "daily"]);
+        assert_eq!(attributes.fqn(), vec!["jaffle", "staging", "orders"]);
+        assert_eq!(attributes.get("label"), Some("Orders".to_string()));
+        assert_eq!(attributes.keys(), vec!["label"]);
+    }
+
+    #[test]
+    fn shared_attributes() {
+        let orders = NodeAttributes::new()
+            .with_resource_type("model")
+            .with_owner("finance")
+            .with_fqn(vec!["jaffle".into(), "marts".into(), "orders".into()])
+            .with_tags(vec!["daily".into(), "pii".into()])
+            .with("tier", "gold");
+        let payments = NodeAttributes::new()
+            .with_resource_type("model")
+            .with_owner("payments")
+            .with_fqn(vec!["jaffle".into(), "marts".into(), "payments".into()])
+            .with_tags(vec!["daily".into()])
+            .with("tier", "gold");
+
+        let shared = NodeAttributes::shared([&orders, &payments]);
+
+        assert_eq!(
+            shared,
+            NodeAttributes::new()
+                .with_resource_type("model")
+                .with_fqn(vec!["jaffle".into(), "marts".into()])
+                .with_tags(vec!["daily".into()])
+                .with("tier", "gold")
+        );
+        assert!(NodeAttributes::shared([]).is_empty());
+    }
+}
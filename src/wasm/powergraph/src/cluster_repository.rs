@@ -1,16 +1,15 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet};
 
-use log::trace;
-
-use crate::clusters::Cluster;
+use crate::{clusters::Cluster, interner::NodeIndex, node_set::NodeSet};
 
 #[derive(Debug, Clone)]
 pub struct ClusterRepository {
-    pub clusters: HashMap<String, Cluster>,
-    overlaps: HashMap<(String, String), OverlapType>,
+    /// The clusters by their items.
+    pub clusters: BTreeMap<NodeSet, Cluster>,
+    overlaps: BTreeMap<(NodeSet, NodeSet), OverlapType>,
 
     // node_cluster_neighbor_map is a mapping between nodes and the clusters their neighbors are in
-    node_cluster_neighbor_map: HashMap<String, HashSet<String>>,
+    node_cluster_neighbor_map: BTreeMap<NodeIndex, BTreeSet<NodeSet>>,
 }
 
 #[derive(Debug, Clone)]
@@ -25,22 +24,21 @@ fn is_equal_clusters(cluster: &Cluster, comparison_cluster: &Cluster) -> bool {
 }
 
 fn is_partial_subset(cluster: &Cluster, comparison_cluster: &Cluster) -> bool {
-    cluster.items.intersection(&comparison_cluster.items).len() > 0
-        && (cluster.items.difference(&comparison_cluster.items).len() == 0
-            || comparison_cluster.items.difference(&cluster.items).len() == 0)
+    !cluster.items.is_disjoint(&comparison_cluster.items)
+        && (cluster.items.is_subset_of(&comparison_cluster.items)
+            || comparison_cluster.items.is_subset_of(&cluster.items))
 }
 
 fn is_subset(cluster: &Cluster, comparison_cluster: &Cluster) -> bool {
-    cluster.items.difference(&comparison_cluster.items).len() == 0
-        && cluster.items.difference(&comparison_cluster.items).len() > 0
+    cluster.items.is_proper_subset_of(&comparison_cluster.items)
 }
 
 impl ClusterRepository {
     pub fn new() -> ClusterRepository {
         ClusterRepository {
-            clusters: HashMap::new(),
-            overlaps: HashMap::new(),
-            node_cluster_neighbor_map: HashMap::new(),
+            clusters: BTreeMap::new(),
+            overlaps: BTreeMap::new(),
+            node_cluster_neighbor_map: BTreeMap::new(),
         }
     }
 
@@ -48,8 +46,8 @@ impl ClusterRepository {
         self.clusters.len()
     }
 
-    fn calculate_overlaps(&self, cluster: &Cluster) -> HashMap<(String, String), OverlapType> {
-        let cluster_id = cluster.get_id().to_string();
+    fn calculate_overlaps(&self, cluster: &Cluster) -> BTreeMap<(NodeSet, NodeSet), OverlapType> {
+        let cluster_id = &cluster.items;
         let overlaps = self
             .clusters
             .iter()
@@ -77,43 +75,43 @@ impl ClusterRepository {
 
                 None
             })
-            .collect::<HashMap<(String, String), OverlapType>>();
+            .collect::<BTreeMap<(NodeSet, NodeSet), OverlapType>>();
         // trace!(
         //     "cluster: {:?}. overlaps: {:?} clusters: {:?}",
         //     cluster,
         //     overlaps,
         //     self.clusters
         // );
-        return overlaps;
+        overlaps
     }
 
     // Add a new Cluster into the ClusterRepository and identify overlapping clusters.
     pub fn add_cluster(&mut self, cluster: &Cluster) {
-        let cluster_id = cluster.get_id().to_string();
+        let cluster_id = &cluster.items;
         self.clusters.insert(cluster_id.clone(), cluster.clone());
 
         let overlaps = self.calculate_overlaps(cluster);
-        self.overlaps.extend(overlaps.into_iter());
+        self.overlaps.extend(overlaps);
 
-        cluster.get_neighbors().into_iter().for_each(|node| {
+        cluster.get_neighbors().iter().for_each(|node| {
             self.node_cluster_neighbor_map
                 .entry(node)
                 .and_modify(|mapping| {
                     mapping.insert(cluster_id.clone());
                 })
                 .or_insert_with(|| {
-                    let mut mapping = HashSet::new();
+                    let mut mapping = BTreeSet::new();
                     mapping.insert(cluster_id.clone());
-                    return mapping;
+                    mapping
                 });
         });
     }
 
-    pub fn get(&self, cluster_id: &String) -> Option<&Cluster> {
+    pub fn get(&self, cluster_id: &NodeSet) -> Option<&Cluster> {
         self.clusters.get(cluster_id)
     }
 
-    pub fn remove(&mut self, cluster_id: &String) {
+    pub fn remove(&mut self, cluster_id: &NodeSet) {
         self.clusters.remove(cluster_id);
 
         for key in self.overlaps.clone().into_keys().filter_map(|(from, to)| {
@@ -130,16 +128,49 @@ impl ClusterRepository {
         }
     }
 
-    pub fn get_sibling_clusters(&self, cluster: &Cluster) -> HashSet<Cluster> {
+    pub fn get_sibling_clusters(&self, cluster: &Cluster) -> BTreeSet<Cluster> {
         cluster
             .get_neighbors()
             .iter()
-            .filter_map(|neighbor| self.node_cluster_neighbor_map.get(neighbor))
+            .filter_map(|neighbor| self.node_cluster_neighbor_map.get(&neighbor))
             .flatten()
             .filter_map(|cluster_id| self.clusters.get(cluster_id))
-            .map(|value| value.clone())
+            .cloned()
             .collect()
     }
+
+    /// Split the clusters into `parts` repositories, each cluster going to the
+    /// part `part` gives its items, along with its overlaps and neighbors.
+    pub fn partition(
+        &self,
+        parts: usize,
+        part: impl Fn(&NodeSet) -> usize,
+    ) -> Vec<ClusterRepository> {
+        let mut repositories: Vec<ClusterRepository> =
+            (0..parts).map(|_| ClusterRepository::new()).collect();
+        for (cluster_id, cluster) in self.clusters.iter() {
+            repositories[part(cluster_id)]
+                .clusters
+                .insert(cluster_id.clone(), cluster.clone());
+        }
+        for ((one, two), overlap) in self.overlaps.iter() {
+            if part(one) == part(two) {
+                repositories[part(one)]
+                    .overlaps
+                    .insert((one.clone(), two.clone()), overlap.clone());
+            }
+        }
+        for (node, cluster_ids) in self.node_cluster_neighbor_map.iter() {
+            for cluster_id in cluster_ids {
+                repositories[part(cluster_id)]
+                    .node_cluster_neighbor_map
+                    .entry(*node)
+                    .or_default()
+                    .insert(cluster_id.clone());
+            }
+        }
+        repositories
+    }
 }
 
 #[cfg(test)]
@@ -148,4 +179,33 @@ mod tests {
 
     #[test]
     fn overlap_calculation() {}
+
+    #[test]
+    fn partition() {
+        let set = |nodes: &[NodeIndex]| nodes.iter().copied().collect::<NodeSet>();
+        let clusters = [
+            Cluster::new(set(&[0]), set(&[1])),
+            Cluster::new(set(&[1]), set(&[0])),
+            Cluster::new(set(&[2]), set(&[3])),
+            Cluster::new(set(&[3]), set(&[2])),
+            Cluster::new(set(&[2, 3]), set(&[])),
+        ];
+        let mut repository = ClusterRepository::new();
+        for cluster in clusters.iter() {
+            repository.add_cluster(cluster);
+        }
+
+        // Nodes 0 and 1 are one part, and nodes 2 and 3 the other.
+        let parts = repository.partition(2, |cluster_id| {
+            cluster_id.iter().next().unwrap() as usize / 2
+        });
+
+        assert_eq!(parts[0].len(), 2);
+        assert_eq!(parts[1].len(), 3);
+        assert_eq!(
+            parts[1].get_sibling_clusters(&clusters[2]),
+            BTreeSet::from([clusters[2].clone()])
+        );
+        assert_eq!(parts[0].get_sibling_clusters(&clusters[2]), BTreeSet::new());
+    }
 }
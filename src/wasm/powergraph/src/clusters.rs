@@ -1,14 +1,29 @@
-use std::collections::HashSet;
-
 use serde::Serialize;
 
-use crate::{sets::Set, unordered_tuple::UnorderedTuple};
+use crate::{
+    interner::Interner,
+    node_set::NodeSet,
+    sets::Set,
+    similarity::{NeighborDirection, Similarity},
+};
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone)]
 pub struct Cluster {
+    pub items: NodeSet,
+    neighbors: NodeSet,
+    /// The neighbors with an edge into the cluster, when known.
+    parents: NodeSet,
+    /// The neighbors with an edge from the cluster, when known.
+    children: NodeSet,
+}
+
+/// A cluster with the ids of its members and neighbors in place of their
+/// interned indices, as it is serialized.
+#[derive(Debug, Clone, Serialize)]
+pub struct NamedCluster {
     pub items: Set<String>,
-    neighbors: Set<String>,
-    id: String,
+    pub neighbors: Set<String>,
+    pub id: String,
 }
 
 impl std::hash::Hash for Cluster {
@@ -33,221 +48,197 @@ impl Eq for Cluster {
     // }
 }
 
-impl Cluster {
-    pub fn new(items: Set<String>, neighbors: Set<String>) -> Cluster {
-        let neighbor_items = neighbors.difference(&items).to_owned();
+impl PartialOrd for Cluster {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
 
-        let id = {
-            let mut values: Vec<&String> = items.items.iter().collect();
-            values.sort();
-            values.iter().map(|s| s.as_str()).collect::<Vec<&str>>().join("-")
-        };
+impl Ord for Cluster {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (&self.items, &self.neighbors).cmp(&(&other.items, &other.neighbors))
+    }
+}
+
+impl Cluster {
+    pub fn new(items: NodeSet, neighbors: NodeSet) -> Cluster {
+        let neighbors = neighbors.difference(&items);
 
         Cluster {
-            items: items.clone(),
-            neighbors: neighbor_items,
-            id,
+            items,
+            neighbors,
+            parents: NodeSet::new(),
+            children: NodeSet::new(),
         }
     }
 
-    /// Compute the similarity score between two Clusters. Similarity is
-    /// The Jaccard index of the parent nodes for each cluster.
-
-    pub fn similarity(&self, other_cluster: &Cluster) -> f32 {
-        let source_nodes: HashSet<&String> =
-            self.items.items.union(&other_cluster.items.items).collect();
-
-        let intersection: HashSet<&String> = self
-            .neighbors
-            .items
-            .intersection(&other_cluster.neighbors.items)
-            .collect::<HashSet<&String>>();
-
-        let intersection_less_source: HashSet<&&String> =
-            intersection.difference(&source_nodes).collect();
-
-        let union: HashSet<&String> = self
-            .neighbors
-            .items
-            .union(&other_cluster.neighbors.items)
-            .collect::<HashSet<&String>>();
-
-        let union_less_source: HashSet<&&String> = union.difference(&source_nodes).collect();
+    /// Create a cluster whose neighbors are split into parents and children,
+    /// so that it can be compared by the direction of its edges.
+    pub fn directed(items: NodeSet, parents: NodeSet, children: NodeSet) -> Cluster {
+        let mut cluster = Cluster::new(items, parents.union(&children));
+        cluster.parents = parents.difference(&cluster.items);
+        cluster.children = children.difference(&cluster.items);
+        cluster
+    }
+
+    /// Compute the similarity score between two Clusters with the given
+    /// measure, over the neighbors in the given direction that are not
+    /// members of either cluster.
+    pub fn similarity_by(
+        &self,
+        other_cluster: &Cluster,
+        similarity: &dyn Similarity,
+        direction: NeighborDirection,
+    ) -> f32 {
+        let (neighbors, other_neighbors) = match direction {
+            NeighborDirection::Any => (&self.neighbors, &other_cluster.neighbors),
+            NeighborDirection::Parents => (&self.parents, &other_cluster.parents),
+            NeighborDirection::Children => (&self.children, &other_cluster.children),
+        };
 
-        let similarity = intersection_less_source.len() as f32 / union_less_source.len() as f32;
+        let source_nodes = self.items.union(&other_cluster.items);
+        let one = neighbors.difference(&source_nodes);
+        let two = other_neighbors.difference(&source_nodes);
 
-        similarity
+        similarity.coefficient(one.intersection_len(&two), one.len(), two.len())
     }
 
     pub fn union(self, other_cluster: &Cluster) -> Cluster {
         let unioned_items = self.items.union(&other_cluster.items);
         let unioned_parents = self.neighbors.union(&other_cluster.neighbors);
 
-        Cluster::new(unioned_items, unioned_parents)
+        let mut cluster = Cluster::new(unioned_items, unioned_parents);
+        cluster.parents = self
+            .parents
+            .union(&other_cluster.parents)
+            .difference(&cluster.items);
+        cluster.children = self
+            .children
+            .union(&other_cluster.children)
+            .difference(&cluster.items);
+        cluster
     }
 
     pub fn difference(self, other_cluster: &Cluster) -> Cluster {
         let items = self.items.difference(&other_cluster.items);
         let parents = self.neighbors.difference(&other_cluster.neighbors);
 
-        Cluster::new(
-            Set::from_set(items.into_iter().cloned().collect()),
-            Set::from_set(parents.into_iter().cloned().collect()),
-        )
+        let mut cluster = Cluster::new(items, parents);
+        cluster.parents = self.parents.difference(&other_cluster.parents);
+        cluster.children = self.children.difference(&other_cluster.children);
+        cluster
     }
 
     pub fn intersection(self, other_cluster: &Cluster) -> Cluster {
         let items = self.items.intersection(&other_cluster.items);
         let parents = self.neighbors.intersection(&other_cluster.neighbors);
 
-        Cluster::new(items, parents)
-    }
-
-    pub fn add_item(&mut self, item: String) {
-        self.items.insert(item);
-    }
-
-    pub fn add_neighbor(&mut self, neighbor: String) {
-        self.neighbors.insert(neighbor);
-    }
-
-    pub fn get_id(&self) -> &str {
-        &self.id
+        let mut cluster = Cluster::new(items, parents);
+        cluster.parents = self.parents.intersection(&other_cluster.parents);
+        cluster.children = self.children.intersection(&other_cluster.children);
+        cluster
     }
 
-    pub fn get_neighbors(&self) -> Vec<String> {
-        return self.neighbors.to_vec();
+    /// The ids of the members of the cluster joined with "-".
+    pub fn get_id(&self, interner: &Interner) -> String {
+        interner.join(&self.items)
     }
 
-    pub fn get_items(&self) -> Vec<String> {
-        return self.items.to_vec();
+    pub fn get_neighbors(&self) -> &NodeSet {
+        &self.neighbors
     }
 
     pub fn size(&self) -> usize {
-        return self.items.len();
+        self.items.len()
     }
-}
 
-pub fn generate_comparison_set(clusters: &Vec<Cluster>) -> Set<UnorderedTuple<String>> {
-    let mut comparison_set: Set<UnorderedTuple<String>> = Set::new();
-
-    for cluster in clusters {
-        for comparison_cluster in clusters {
-            if cluster == comparison_cluster {
-                continue;
-            }
-
-            let cluster_parents = Set::from_iter(cluster.get_neighbors());
-            let comparison_cluster_parents = Set::from_iter(comparison_cluster.get_neighbors());
-
-            if cluster_parents
-                .intersection(&comparison_cluster_parents)
-                .len()
-                == 0
-            {
-                continue;
-            }
-
-            comparison_set.insert(UnorderedTuple {
-                one: cluster.get_id().to_owned(),
-                two: comparison_cluster.get_id().to_owned(),
-            });
+    pub fn named(&self, interner: &Interner) -> NamedCluster {
+        let names =
+            |set: &NodeSet| Set::from_set(interner.names(set).map(str::to_string).collect());
+
+        NamedCluster {
+            items: names(&self.items),
+            neighbors: names(&self.neighbors),
+            id: self.get_id(interner),
         }
     }
-
-    return comparison_set;
 }
 
 #[cfg(test)]
 mod tests {
 
     use crate::{
-        clusters::{generate_comparison_set, Cluster, UnorderedTuple},
-        sets::Set,
+        clusters::Cluster,
+        interner::NodeIndex,
+        node_set::NodeSet,
+        similarity::{Jaccard, NeighborDirection, SimilarityMeasure},
     };
 
+    fn set(nodes: &[NodeIndex]) -> NodeSet {
+        nodes.iter().copied().collect()
+    }
+
     #[test]
     fn trivial_positive_case() {
-        let set1 = Cluster::new(
-            Set::from_iter(vec!["2".to_string()]),
-            Set::from_iter(vec!["1".to_string()]),
-        );
-        let set2 = Cluster::new(
-            Set::from_iter(vec!["2".to_string()]),
-            Set::from_iter(vec!["1".to_string()]),
-        );
+        let set1 = Cluster::new(set(&[2]), set(&[1]));
+        let set2 = Cluster::new(set(&[2]), set(&[1]));
 
-        assert_eq!(set1.similarity(&set2), 1.0_f32)
+        assert_eq!(
+            set1.similarity_by(&set2, &Jaccard, NeighborDirection::Any),
+            1.0_f32
+        )
     }
 
     #[test]
     fn trivial_negative_case() {
-        let set1 = Cluster::new(
-            Set::from_iter(vec!["2".to_string()]),
-            Set::from_iter(vec!["1".to_string()]),
-        );
-        let set2 = Cluster::new(
-            Set::from_iter(vec!["3".to_string()]),
-            Set::from_iter(vec!["2".to_string()]),
-        );
+        let set1 = Cluster::new(set(&[2]), set(&[1]));
+        let set2 = Cluster::new(set(&[3]), set(&[2]));
 
-        assert_eq!(set1.similarity(&set2), 0.0_f32)
+        assert_eq!(
+            set1.similarity_by(&set2, &Jaccard, NeighborDirection::Any),
+            0.0_f32
+        )
     }
 
     #[test]
     fn nontrivial_case() {
-        let set1 = Cluster::new(
-            Set::from_iter(vec!["2".to_string(), "3".to_string()]),
-            Set::from_iter(vec!["0".to_string(), "1".to_string()]),
-        );
-        let set2 = Cluster::new(
-            Set::from_iter(vec!["4".to_string(), "5".to_string()]),
-            Set::from_iter(vec![
-                "0".to_string(),
-                "6".to_string(),
-                "5".to_string(),
-                "7".to_string(),
-            ]),
-        );
+        let set1 = Cluster::new(set(&[2, 3]), set(&[0, 1]));
+        let set2 = Cluster::new(set(&[4, 5]), set(&[0, 6, 5, 7]));
 
-        assert_eq!(set1.similarity(&set2), 0.25_f32)
+        assert_eq!(
+            set1.similarity_by(&set2, &Jaccard, NeighborDirection::Any),
+            0.25_f32
+        )
     }
 
     #[test]
-    fn comparison_sets() {
-        let set1 = Cluster::new(
-            Set::from_iter(vec!["1".to_string()]),
-            Set::from_iter(vec![]),
-        );
-        let set2 = Cluster::new(
-            Set::from_iter(vec!["2".to_string()]),
-            Set::from_iter(vec!["1".to_string()]),
-        );
-        let set3 = Cluster::new(
-            Set::from_iter(vec!["3".to_string()]),
-            Set::from_iter(vec!["1".to_string()]),
+    fn directed_similarity() {
+        // Two models built from the same source, feeding different models.
+        let source = 0;
+        let set1 = Cluster::directed(set(&[1]), set(&[source]), set(&[3]));
+        let set2 = Cluster::directed(set(&[2]), set(&[source]), set(&[4, 5]));
+
+        let similarity = |measure, direction| set1.similarity_by(&set2, &measure, direction);
+
+        assert_eq!(
+            similarity(SimilarityMeasure::Jaccard, NeighborDirection::Any),
+            0.25_f32
         );
-        let set4 = Cluster::new(
-            Set::from_iter(vec!["4".to_string()]),
-            Set::from_iter(vec!["2".to_string()]),
+        assert_eq!(
+            similarity(SimilarityMeasure::SorensenDice, NeighborDirection::Any),
+            0.4_f32
         );
-        let clusters = vec![set1, set2, set3, set4];
-
-        let comparison_set = generate_comparison_set(&clusters);
         assert_eq!(
-            comparison_set,
-            Set::from_iter(vec![UnorderedTuple {
-                one: "2".to_string(),
-                two: "3".to_string()
-            }])
+            similarity(SimilarityMeasure::Jaccard, NeighborDirection::Parents),
+            1.0_f32
         );
-
         assert_eq!(
-            comparison_set,
-            Set::from_iter(vec![UnorderedTuple {
-                one: "3".to_string(),
-                two: "2".to_string()
-            }])
+            similarity(SimilarityMeasure::Jaccard, NeighborDirection::Children),
+            0.0_f32
         );
+
+        let union = set1.clone().union(&set2);
+        assert_eq!(union.parents, set(&[source]));
+        assert_eq!(union.children.len(), 3);
     }
 }
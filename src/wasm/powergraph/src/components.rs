@@ -0,0 +1,193 @@
+//! The clusters of each connected component of a graph, merged by similarity
+//! independently of the other components. Clusters in different components
+//! share no neighbors, so they are never compared, and each component merges
+//! its clusters in the same order whether it runs alone or alongside others.
+
+use std::collections::BTreeMap;
+
+use crate::cluster_repository::ClusterRepository;
+use crate::clusters::Cluster;
+use crate::node_set::NodeSet;
+use crate::options::DecompositionOptions;
+use crate::parallel;
+use crate::similarity::Similarity;
+use crate::similarity_matrix::SimilarityMatrix;
+use crate::unordered_tuple::UnorderedTuple;
+use crate::{console_debug, console_log, PowerGraph};
+
+pub(crate) struct Component {
+    c_prime: ClusterRepository,
+    cluster_map: BTreeMap<NodeSet, Cluster>,
+    similarity_matrix: SimilarityMatrix,
+    comparisons: std::vec::IntoIter<UnorderedTuple<Cluster>>,
+    /// Whether every pair of clusters has been compared, so that the most
+    /// similar are being merged.
+    merging: bool,
+    done: bool,
+    /// The clusters formed by merges since they were last taken.
+    merged: Vec<Cluster>,
+}
+
+/// The components of `graph`, in order of their lowest node, each with the
+/// clusters of `clusters` within it.
+pub(crate) fn components(graph: &PowerGraph, clusters: &ClusterRepository) -> Vec<Component> {
+    let nodes = graph.edges.components();
+    let mut component_of = vec![0; graph.interner.len()];
+    for (component, nodes) in nodes.iter().enumerate() {
+        for node in nodes.iter() {
+            component_of[node as usize] = component;
+        }
+    }
+
+    clusters
+        .partition(nodes.len(), |cluster_id| {
+            let node = cluster_id.iter().next().expect("clusters are not empty");
+            component_of[node as usize]
+        })
+        .into_iter()
+        .map(|c_prime| Component::new(graph, c_prime))
+        .collect()
+}
+
+impl Component {
+    fn new(graph: &PowerGraph, c_prime: ClusterRepository) -> Component {
+        let comparisons = graph.generate_graph_comparison_set(
+            &c_prime.clusters.values().cloned().collect::<Vec<Cluster>>(),
+        );
+        Component {
+            cluster_map: c_prime.clusters.clone(),
+            c_prime,
+            similarity_matrix: SimilarityMatrix::new(),
+            comparisons: comparisons.into_iter(),
+            merging: false,
+            done: false,
+            merged: Vec::new(),
+        }
+    }
+
+    /// The pairs of clusters left to compare.
+    pub(crate) fn comparisons(&self) -> usize {
+        self.comparisons.len()
+    }
+
+    /// Whether no more clusters are similar enough to merge.
+    pub(crate) fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// The clusters formed by merges since this was last called, in the order
+    /// they were formed.
+    pub(crate) fn take_merged(&mut self) -> Vec<Cluster> {
+        std::mem::take(&mut self.merged)
+    }
+
+    /// Compare up to `max_work` pairs of clusters or, once every pair is
+    /// compared, merge up to `max_work` pairs of the most similar clusters.
+    pub(crate) fn step(
+        &mut self,
+        options: &DecompositionOptions,
+        similarity: &dyn Similarity,
+        max_work: usize,
+    ) {
+        let mut work = 0;
+        while work < max_work && !self.done {
+            if self.merging {
+                self.done = !self.merge(options, similarity);
+                work += 1;
+                continue;
+            }
+
+            let comparison_sets: Vec<UnorderedTuple<Cluster>> =
+                self.comparisons.by_ref().take(max_work - work).collect();
+            if comparison_sets.is_empty() {
+                console_debug!(
+                    "Similarity matrix populated with {:?} entries.",
+                    self.similarity_matrix.len()
+                );
+                self.merging = true;
+                continue;
+            }
+            work += comparison_sets.len();
+
+            let similarities = parallel::map(comparison_sets, |comparison_set| {
+                let similarity = comparison_set.one.similarity_by(
+                    &comparison_set.two,
+                    similarity,
+                    options.neighbor_direction,
+                );
+                (comparison_set, similarity)
+            });
+            for (comparison_set, similarity) in similarities {
+                self.similarity_matrix.set_similarity(
+                    UnorderedTuple {
+                        one: comparison_set.one.items,
+                        two: comparison_set.two.items,
+                    },
+                    similarity,
+                );
+            }
+        }
+    }
+
+    /// Merge the two most similar clusters, returning whether they were
+    /// similar enough to.
+    fn merge(&mut self, options: &DecompositionOptions, similarity: &dyn Similarity) -> bool {
+        // Find the two clusters with maximum similarity
+        let max_similarity = match self.similarity_matrix.get_max_similarity() {
+            Some(max_similarity)
+                if self.c_prime.len() > 0 && max_similarity.1 >= options.similarity_threshold =>
+            {
+                max_similarity
+            }
+            _ => return false,
+        };
+
+        console_log!("Max similarity: {:?}", max_similarity);
+        console_log!("Clusters remaining to process: {:?}", self.c_prime.len());
+
+        let cluster = self.cluster_map.get(&max_similarity.0.one).unwrap();
+        let comparison_cluster = self.cluster_map.get(&max_similarity.0.two).unwrap();
+
+        self.c_prime.remove(&cluster.items);
+        self.c_prime.remove(&comparison_cluster.items);
+
+        self.similarity_matrix.remove_element(&cluster.items);
+        self.similarity_matrix
+            .remove_element(&comparison_cluster.items);
+
+        let unioned_cluster = cluster.clone().union(comparison_cluster);
+
+        // Add new cluster to everything!
+        self.merged.push(unioned_cluster.clone());
+        self.c_prime.add_cluster(&unioned_cluster);
+        self.cluster_map
+            .insert(unioned_cluster.items.clone(), unioned_cluster.clone());
+
+        // Calculate new similarities for the added element.
+        let siblings: Vec<Cluster> = self
+            .c_prime
+            .get_sibling_clusters(&unioned_cluster)
+            .into_iter()
+            .filter(|comparison_cluster| unioned_cluster != *comparison_cluster)
+            .collect();
+        let similarities = parallel::map(siblings, |comparison_cluster| {
+            let similarity = unioned_cluster.similarity_by(
+                &comparison_cluster,
+                similarity,
+                options.neighbor_direction,
+            );
+            (comparison_cluster, similarity)
+        });
+        for (comparison_cluster, similarity) in similarities {
+            self.similarity_matrix.set_similarity(
+                UnorderedTuple {
+                    one: unioned_cluster.items.clone(),
+                    two: comparison_cluster.items,
+                },
+                similarity,
+            );
+        }
+
+        true
+    }
+}
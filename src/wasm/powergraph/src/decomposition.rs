@@ -0,0 +1,794 @@
+//! The phases of a decomposition as a resumable state machine, so that a
+//! decomposition can be run in slices, such as from `requestIdleCallback`.
+
+use std::collections::{BTreeSet, BinaryHeap, HashSet};
+
+use itertools::Itertools;
+use wasm_bindgen::prelude::*;
+
+use crate::cluster_repository::ClusterRepository;
+use crate::clusters::Cluster;
+use crate::components::{components, Component};
+use crate::edge_coverage::EdgeCoverage;
+use crate::error::PowerGraphError;
+use crate::node_set::NodeSet;
+use crate::options::{CandidateScoring, DecompositionOptions};
+use crate::parallel;
+use crate::progress::{checkpoint, NoProgress, Progress};
+use crate::similarity::Similarity;
+use crate::stats::PhaseTimer;
+use crate::{
+    console_debug, console_log, Edge, PowerEdge, PowerEdgeCandidate,
+    PowerEdgeCandidateProcessorOutput, PowerGraph, PowerNode,
+};
+
+/// The phases of a decomposition, in the order they run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Phase {
+    Singletons,
+    Similarity,
+    Neighborhoods,
+    Candidates,
+    PowerEdges,
+    ResidualEdges,
+    Aggregation,
+    Done,
+}
+
+impl Phase {
+    fn name(&self) -> &'static str {
+        match self {
+            Phase::Singletons => "singletons",
+            Phase::Similarity => "similarity",
+            Phase::Neighborhoods => "neighborhoods",
+            Phase::Candidates => "candidates",
+            Phase::PowerEdges => "power_edges",
+            Phase::ResidualEdges => "residual_edges",
+            Phase::Aggregation => "aggregation",
+            Phase::Done => "done",
+        }
+    }
+
+    fn next(&self) -> Phase {
+        match self {
+            Phase::Singletons => Phase::Similarity,
+            Phase::Similarity => Phase::Neighborhoods,
+            Phase::Neighborhoods => Phase::Candidates,
+            Phase::Candidates => Phase::PowerEdges,
+            Phase::PowerEdges => Phase::ResidualEdges,
+            Phase::ResidualEdges => Phase::Aggregation,
+            Phase::Aggregation | Phase::Done => Phase::Done,
+        }
+    }
+}
+
+/// Everything a decomposition carries from one unit of work to the next.
+pub(crate) struct DecompositionState {
+    phase: Phase,
+    /// Whether the current phase has been set up.
+    started: bool,
+    /// The number of items of the current phase processed so far.
+    position: usize,
+    /// The number of items of the current phase processed at its last
+    /// progress checkpoint.
+    checkpointed: usize,
+    timer: PhaseTimer,
+
+    cluster_repository: ClusterRepository,
+    /// The connected components still merging their clusters.
+    components: Vec<Component>,
+    cluster_count: usize,
+
+    neighborhood_order: usize,
+    cluster_keys: Vec<NodeSet>,
+
+    /// The clusters, by index into `cluster_keys`, with each node as a
+    /// neighbor, by node index.
+    adjacent_clusters: Vec<Vec<usize>>,
+    edge_candidates: BinaryHeap<PowerEdgeCandidate>,
+    queued_candidates: HashSet<PowerEdgeCandidate>,
+    completed_candidates: HashSet<PowerEdgeCandidate>,
+    edge_coverage: EdgeCoverage,
+
+    covered_edges: HashSet<(String, String)>,
+    edges: std::vec::IntoIter<Edge>,
+}
+
+impl DecompositionState {
+    pub(crate) fn new() -> DecompositionState {
+        DecompositionState {
+            phase: Phase::Singletons,
+            started: false,
+            position: 0,
+            checkpointed: 0,
+            timer: PhaseTimer::start(),
+            cluster_repository: ClusterRepository::new(),
+            components: Vec::new(),
+            cluster_count: 0,
+            neighborhood_order: 1,
+            cluster_keys: Vec::new(),
+            adjacent_clusters: Vec::new(),
+            edge_candidates: BinaryHeap::new(),
+            queued_candidates: HashSet::new(),
+            completed_candidates: HashSet::new(),
+            edge_coverage: EdgeCoverage::new(),
+            covered_edges: HashSet::new(),
+            edges: Vec::new().into_iter(),
+        }
+    }
+
+    pub(crate) fn is_done(&self) -> bool {
+        self.phase == Phase::Done
+    }
+
+    /// Process up to `max_work` items, such as nodes, clusters or power edge
+    /// candidates, across as many phases as they take. A cluster pair in each
+    /// connected component counts as one item. With the `parallel` feature,
+    /// cluster pairs and clusters are processed in batches, each of which
+    /// counts as one item. Returns whether the decomposition is done. Time
+    /// between steps is not counted towards the timings of the phases.
+    pub(crate) fn step(
+        &mut self,
+        graph: &mut PowerGraph,
+        options: &DecompositionOptions,
+        similarity: &dyn Similarity,
+        progress: &mut dyn Progress,
+        max_work: usize,
+    ) -> Result<bool, PowerGraphError> {
+        self.timer.resume();
+
+        let mut work = 0;
+        while work < max_work && !self.is_done() {
+            if !self.started {
+                self.start_phase(graph, options);
+                self.started = true;
+            }
+
+            let finished = match self.phase {
+                Phase::Singletons => self.singleton(graph),
+                Phase::Similarity => self.merge(options, similarity, progress)?,
+                Phase::Neighborhoods => self.neighborhood(graph, options, similarity),
+                Phase::Candidates => self.candidate(graph, options, progress)?,
+                Phase::PowerEdges => self.power_edge(graph, options, progress)?,
+                Phase::ResidualEdges => self.residual_edge(graph),
+                Phase::Aggregation => self.aggregate(graph),
+                Phase::Done => true,
+            };
+            work += 1;
+
+            if finished {
+                self.timer.finish(self.phase.name());
+                self.phase = self.phase.next();
+                self.started = false;
+                self.position = 0;
+                self.checkpointed = 0;
+
+                if self.is_done() {
+                    graph.timings = self.timer.timings().to_vec();
+                    console_log!(
+                        "Complete!\nPower Nodes: {:?}\n\tPower Edges: {:?}",
+                        graph.power_nodes,
+                        graph.power_edges
+                    );
+                }
+            }
+        }
+
+        self.timer.pause();
+        Ok(self.is_done())
+    }
+
+    /// Report that `processed` of `total` items of the current phase have been
+    /// processed, as `checkpoint` does.
+    fn checkpoint(
+        &mut self,
+        progress: &mut dyn Progress,
+        processed: usize,
+        total: usize,
+    ) -> Result<(), PowerGraphError> {
+        let previous = std::mem::replace(&mut self.checkpointed, processed);
+        checkpoint(progress, self.phase.name(), previous, processed, total)
+    }
+
+    /// Set up the current phase from the results of the phases before it.
+    fn start_phase(&mut self, graph: &mut PowerGraph, options: &DecompositionOptions) {
+        match self.phase {
+            Phase::Singletons => {
+                graph.directed = options.directed;
+
+                // Add all nodes to c and c_prime as singleton clusters.
+                console_log!("Identify singleton clusters.");
+            }
+            Phase::Similarity => {
+                self.components = components(graph, &self.cluster_repository);
+                console_log!(
+                    "Identified {:?} sets of clusters for comparison in {:?} components.",
+                    self.components
+                        .iter()
+                        .map(Component::comparisons)
+                        .sum::<usize>(),
+                    self.components.len()
+                );
+
+                // Each merge replaces two clusters with one, so there are fewer
+                // merges than clusters.
+                self.cluster_count = self.cluster_repository.len();
+            }
+            Phase::Neighborhoods => {
+                // Add the neighborhoods of each cluster as clusters in `c`, once
+                // for each order of neighborhood.
+                self.neighborhood_order = 1;
+                if options.neighborhood_depth > 0 {
+                    self.start_neighborhood_order();
+                }
+            }
+            Phase::Candidates => {
+                let singletons: Vec<PowerNode> = self
+                    .cluster_repository
+                    .clusters
+                    .values()
+                    .filter(|cluster| cluster.size() <= 1)
+                    .map(|cluster| {
+                        console_debug!(
+                            "{:?} is a singleton. Adding to PowerNodes.",
+                            cluster.get_id(&graph.interner)
+                        );
+                        PowerNode::new(cluster.clone(), &graph.interner)
+                    })
+                    .collect();
+                for singleton in singletons {
+                    graph.add_power_node(singleton);
+                }
+
+                // Only clusters joined by an edge can form a biclique, so each
+                // cluster is paired with the clusters that have one of its
+                // members as a neighbor, and with itself.
+                self.cluster_keys = self.cluster_repository.clusters.keys().cloned().collect();
+                self.adjacent_clusters = vec![Vec::new(); graph.interner.len()];
+                for (index, key) in self.cluster_keys.iter().enumerate() {
+                    for node in self.cluster_repository.clusters[key].get_neighbors().iter() {
+                        self.adjacent_clusters[node as usize].push(index);
+                    }
+                }
+                console_log!(
+                    "Pairing {:?} clusters with their adjacent clusters for power edge candidates.",
+                    self.cluster_keys.len()
+                );
+            }
+            Phase::PowerEdges => {}
+            Phase::ResidualEdges => {
+                // For all remaining edges not yet covered by power edges, create
+                // new power edges.
+                console_debug!("PowerEdges: {:?}", graph.power_edges);
+                self.covered_edges.clear();
+                for power_edge in graph.power_edges.iter() {
+                    if let Some(edges) = graph.expand_power_edge(power_edge) {
+                        for edge in edges {
+                            if !graph.directed {
+                                self.covered_edges
+                                    .insert((edge.to.clone(), edge.from.clone()));
+                            }
+                            self.covered_edges.insert((edge.from, edge.to));
+                        }
+                    }
+                }
+                console_debug!("Covered edges: {:?}", self.covered_edges);
+
+                self.edges = graph.edges.clone().into_iter().collect_vec().into_iter();
+            }
+            Phase::Aggregation | Phase::Done => {}
+        }
+    }
+
+    /// Add the next node as a singleton cluster.
+    fn singleton(&mut self, graph: &mut PowerGraph) -> bool {
+        let Some(node) = graph.nodes.get(self.position) else {
+            console_log!(
+                "Singleton cluster identification complete. Found {:?} clusters.",
+                self.cluster_repository.len()
+            );
+            return true;
+        };
+        self.position += 1;
+
+        let node = graph
+            .interner
+            .index(&node.id)
+            .expect("every node is interned");
+        let cluster_nodes = Cluster::directed(
+            NodeSet::single(node),
+            graph.edges.parents(node).clone(),
+            graph.edges.children(node).clone(),
+        );
+        self.cluster_repository.add_cluster(&cluster_nodes);
+        false
+    }
+
+    /// Compare or merge up to a batch of pairs of clusters in each connected
+    /// component, adding the clusters formed to the cluster repository.
+    fn merge(
+        &mut self,
+        options: &DecompositionOptions,
+        similarity: &dyn Similarity,
+        progress: &mut dyn Progress,
+    ) -> Result<bool, PowerGraphError> {
+        let cluster_count = self.cluster_count;
+        if self.components.is_empty() {
+            self.checkpoint(progress, cluster_count, cluster_count)?;
+            return Ok(true);
+        }
+        self.checkpoint(progress, self.position, cluster_count)?;
+
+        parallel::for_each_mut(&mut self.components, |component| {
+            component.step(options, similarity, parallel::BATCH)
+        });
+
+        // Components are disjoint, so the clusters they form are added in the
+        // order of the components without changing the decomposition.
+        for component in self.components.iter_mut() {
+            for cluster in component.take_merged() {
+                self.cluster_repository.add_cluster(&cluster);
+                self.position += 1;
+            }
+        }
+        self.components.retain(|component| !component.is_done());
+
+        Ok(false)
+    }
+
+    fn start_neighborhood_order(&mut self) {
+        console_log!(
+            "Adding order {:?} neighborhoods as clusters.",
+            self.neighborhood_order
+        );
+        self.cluster_keys = self.cluster_repository.clusters.keys().cloned().collect();
+        self.position = 0;
+    }
+
+    /// Add the neighborhood of the next cluster as a cluster, if it is similar
+    /// enough to the cluster.
+    fn neighborhood(
+        &mut self,
+        graph: &PowerGraph,
+        options: &DecompositionOptions,
+        similarity: &dyn Similarity,
+    ) -> bool {
+        if self.neighborhood_order > options.neighborhood_depth {
+            return true;
+        }
+        if self.position == self.cluster_keys.len() {
+            self.neighborhood_order += 1;
+            if self.neighborhood_order <= options.neighborhood_depth {
+                self.start_neighborhood_order();
+            }
+            return false;
+        }
+
+        let cluster = self
+            .cluster_repository
+            .clusters
+            .get(&self.cluster_keys[self.position])
+            .unwrap()
+            .clone();
+        self.position += 1;
+
+        let items = cluster.get_neighbors().clone();
+        let mut parents = NodeSet::new();
+        let mut children = NodeSet::new();
+        for node in items.iter() {
+            parents.union_with(graph.edges.parents(node));
+            children.union_with(graph.edges.children(node));
+        }
+        let neighborhood_cluster = Cluster::directed(items, parents, children);
+
+        let neighbor_similarity = cluster.similarity_by(
+            &neighborhood_cluster,
+            similarity,
+            options.neighbor_direction,
+        );
+
+        if neighbor_similarity >= options.similarity_threshold {
+            console_debug!(
+                "The similarity between {:?} and {:?} is {:?}. Adding to `c`.",
+                cluster,
+                neighborhood_cluster,
+                neighbor_similarity
+            );
+
+            self.cluster_repository.add_cluster(&neighborhood_cluster);
+        }
+        false
+    }
+
+    /// The clusters after `one` in `cluster_keys` that `one` may form a power
+    /// edge candidate with: those adjacent to a member of `one` and disjoint
+    /// from it, and `one` itself.
+    fn partners(&self, one: usize, options: &DecompositionOptions) -> Vec<usize> {
+        let cluster = &self.cluster_repository.clusters[&self.cluster_keys[one]];
+        if !options.allows_size(cluster.size()) {
+            return vec![];
+        }
+
+        let mut partners: BTreeSet<usize> = cluster
+            .items
+            .iter()
+            .flat_map(|node| self.adjacent_clusters[node as usize].iter().copied())
+            .filter(|&two| {
+                two > one
+                    && cluster.items.is_disjoint(
+                        &self.cluster_repository.clusters[&self.cluster_keys[two]].items,
+                    )
+            })
+            .collect();
+        partners.insert(one);
+        partners.into_iter().collect()
+    }
+
+    /// Queue the power edge candidates formed by the next clusters with their
+    /// partners.
+    fn candidate(
+        &mut self,
+        graph: &PowerGraph,
+        options: &DecompositionOptions,
+        progress: &mut dyn Progress,
+    ) -> Result<bool, PowerGraphError> {
+        let cluster_count = self.cluster_keys.len();
+        self.checkpoint(progress, self.position, cluster_count)?;
+        if self.position == cluster_count {
+            return Ok(true);
+        }
+
+        let clusters: Vec<usize> =
+            (self.position..cluster_count.min(self.position + parallel::BATCH)).collect();
+        self.position += clusters.len();
+
+        let candidates = parallel::map(clusters, |one| {
+            self.partners(one, options)
+                .into_iter()
+                .flat_map(|two| self.pair(graph, options, one, two))
+                .collect::<Vec<PowerEdgeCandidate>>()
+        });
+        for candidate in candidates.into_iter().flatten() {
+            self.queued_candidates.insert(candidate.clone());
+            self.edge_candidates.push(candidate);
+        }
+
+        Ok(false)
+    }
+
+    /// The power edge candidates formed by a pair of clusters, by index into
+    /// `cluster_keys`.
+    fn pair(
+        &self,
+        graph: &PowerGraph,
+        options: &DecompositionOptions,
+        one: usize,
+        two: usize,
+    ) -> Vec<PowerEdgeCandidate> {
+        let cluster_one = &self.cluster_repository.clusters[&self.cluster_keys[one]];
+        let cluster_two = &self.cluster_repository.clusters[&self.cluster_keys[two]];
+        let mut candidates = vec![];
+
+        if !options.allows_size(cluster_one.size()) || !options.allows_size(cluster_two.size()) {
+            return candidates;
+        }
+
+        let node_intersection = cluster_one.items.intersection(&cluster_two.items);
+        let node_union = cluster_one.items.union(&cluster_two.items);
+
+        // In directed mode, a pair of clusters may form a biclique in either
+        // direction, or both.
+        let orientations = if options.directed {
+            vec![(cluster_one, cluster_two), (cluster_two, cluster_one)]
+        } else {
+            vec![(cluster_one, cluster_two)]
+        };
+        let bicliques: Vec<(&Cluster, &Cluster)> = if !node_intersection.is_empty() {
+            vec![]
+        } else {
+            orientations
+                .into_iter()
+                .filter(|(from, to)| graph.clusters_form_biclique(from, to, options))
+                .collect()
+        };
+
+        for (from, to) in bicliques {
+            let size = match options.scoring {
+                CandidateScoring::Edges => graph.subgraph(&node_union).len() as f32,
+                CandidateScoring::Nodes => node_union.len() as f32,
+            };
+
+            candidates.push(PowerEdgeCandidate {
+                from: from.clone(),
+                to: to.clone(),
+                size,
+            });
+        }
+
+        if options.allow_cliques
+            && cluster_one == cluster_two
+            && graph.clusters_are_clique(cluster_one, cluster_two, options.directed)
+        {
+            let size = match options.scoring {
+                CandidateScoring::Edges => graph.subgraph(&node_union).len() as f32 / 2_f32,
+                CandidateScoring::Nodes => node_union.len() as f32,
+            };
+            candidates.push(PowerEdgeCandidate {
+                from: cluster_one.clone(),
+                to: cluster_two.clone(),
+                size,
+            });
+        }
+
+        candidates
+    }
+
+    /// Process the largest remaining power edge candidate.
+    fn power_edge(
+        &mut self,
+        graph: &mut PowerGraph,
+        options: &DecompositionOptions,
+        progress: &mut dyn Progress,
+    ) -> Result<bool, PowerGraphError> {
+        // Processing a candidate may queue more, so the total grows as it runs.
+        let processed = self.position;
+        let Some(edge_candidate) = self.edge_candidates.pop() else {
+            self.checkpoint(progress, processed, processed)?;
+            return Ok(true);
+        };
+        let total = processed + self.edge_candidates.len() + 1;
+        self.checkpoint(progress, processed, total)?;
+        self.position += 1;
+
+        let candidate_processor_results = graph.process_edge_candidate(
+            &edge_candidate,
+            &self.cluster_repository,
+            &self.edge_coverage,
+            options,
+        );
+
+        for result in candidate_processor_results {
+            match result {
+                PowerEdgeCandidateProcessorOutput::NewPowerEdgeCandidate(candidate) => {
+                    // Don't process the same edge candidate twice.
+                    if self.completed_candidates.contains(&candidate) {
+                        continue;
+                    }
+
+                    // Don't process invalid edges
+                    if candidate.from.items.is_empty() || candidate.to.items.is_empty() {
+                        continue;
+                    }
+
+                    // Don't process edges the options rule out.
+                    if !options.allows_size(candidate.from.size())
+                        || !options.allows_size(candidate.to.size())
+                        || (!options.allow_cliques && candidate.from == candidate.to)
+                    {
+                        continue;
+                    }
+
+                    self.cluster_repository.add_cluster(&candidate.from);
+                    self.cluster_repository.add_cluster(&candidate.to);
+
+                    if !self.queued_candidates.contains(&candidate) {
+                        self.queued_candidates.insert(candidate.clone());
+                        self.edge_candidates.push(candidate.clone());
+                    }
+                    self.completed_candidates.insert(candidate);
+                }
+                PowerEdgeCandidateProcessorOutput::NewPowerNode(power_node) => {
+                    self.cluster_repository.add_cluster(&power_node.cluster);
+
+                    graph.add_power_node(*power_node);
+                }
+                PowerEdgeCandidateProcessorOutput::NewPowerEdge(power_edge) => {
+                    self.edge_coverage.add(
+                        graph.power_edges.len(),
+                        graph.power_edge_subgraph(&power_edge),
+                    );
+                    graph.power_edges.push(power_edge)
+                }
+            }
+        }
+        console_log!("Candidate Count: {:?}", self.edge_candidates.len());
+
+        Ok(false)
+    }
+
+    /// Add the next edge as a plain power edge if no power edge covers it.
+    fn residual_edge(&mut self, graph: &mut PowerGraph) -> bool {
+        let Some(edge) = self.edges.next() else {
+            return true;
+        };
+
+        if !self
+            .covered_edges
+            .contains(&(edge.from.clone(), edge.to.clone()))
+        {
+            graph.power_edges.push(PowerEdge {
+                from: edge.from,
+                to: edge.to,
+                residual: true,
+                negative_edges: Vec::new(),
+            })
+        }
+        false
+    }
+
+    fn aggregate(&mut self, graph: &mut PowerGraph) -> bool {
+        // The same power node may have been found for several power edges.
+        let power_nodes = graph
+            .power_nodes
+            .drain(..)
+            .unique_by(|power_node| power_node.id.clone())
+            .collect();
+        graph.set_power_nodes(power_nodes);
+
+        graph.aggregate_test_counts();
+        graph.aggregate_projects();
+        graph.aggregate_attributes();
+        graph.aggregate_containment();
+        true
+    }
+}
+
+/// A decomposition that runs a slice at a time, so that it can share a
+/// thread with other work, such as from a Web Worker or `requestIdleCallback`.
+#[wasm_bindgen]
+pub struct Decomposition {
+    powergraph: PowerGraph,
+    options: DecompositionOptions,
+    state: DecompositionState,
+}
+
+#[wasm_bindgen]
+impl Decomposition {
+    /// Start decomposing `powergraph` with the given options, failing if the
+    /// options are out of range.
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        powergraph: PowerGraph,
+        options: &DecompositionOptions,
+    ) -> Result<Decomposition, JsError> {
+        options.validate()?;
+
+        Ok(Decomposition {
+            powergraph,
+            options: *options,
+            state: DecompositionState::new(),
+        })
+    }
+
+    /// Advance the decomposition by up to `max_work` items, such as nodes,
+    /// cluster pairs or power edge candidates. The connected components of the
+    /// graph are merged side by side, a cluster pair of each counting as one
+    /// item. In native builds with the `parallel` feature, a batch of cluster
+    /// pairs or clusters counts as one item. Returns whether it is done.
+    pub fn step(&mut self, max_work: usize) -> bool {
+        self.state
+            .step(
+                &mut self.powergraph,
+                &self.options,
+                &self.options.similarity,
+                &mut NoProgress,
+                max_work,
+            )
+            .expect("decompositions without progress are never cancelled")
+    }
+
+    /// The name of the phase the decomposition is in, or "done".
+    #[wasm_bindgen(getter)]
+    pub fn phase(&self) -> String {
+        self.state.phase.name().to_string()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn done(&self) -> bool {
+        self.state.is_done()
+    }
+
+    /// The power graph as decomposed so far. Until the decomposition is done,
+    /// it may repeat power nodes, and power nodes are not yet aggregated.
+    pub fn powergraph(&self) -> PowerGraph {
+        self.powergraph.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::Manifest;
+
+    fn powergraph() -> PowerGraph {
+        let manifest: Manifest = include_str!("../../../../assets/manifest.small.json")
+            .parse()
+            .unwrap();
+        let (nodes, edges) = manifest.to_graph().unwrap();
+        PowerGraph::new(nodes, edges)
+    }
+
+    #[test]
+    fn step_through_decomposition() {
+        let mut decomposed = powergraph();
+        decomposed.decompose();
+
+        let mut decomposition =
+            Decomposition::new(powergraph(), &DecompositionOptions::default()).unwrap();
+        let mut phases = vec![decomposition.phase()];
+        let mut power_edge_counts = vec![];
+        while !decomposition.step(1) {
+            if phases.last() != Some(&decomposition.phase()) {
+                phases.push(decomposition.phase());
+            }
+            power_edge_counts.push(decomposition.powergraph().power_edge_count());
+        }
+
+        assert!(decomposition.done());
+        assert_eq!(
+            phases,
+            vec![
+                "singletons",
+                "similarity",
+                "neighborhoods",
+                "candidates",
+                "power_edges",
+                "residual_edges",
+                "aggregation",
+            ]
+        );
+        // The partial power graph only gains power edges.
+        assert!(power_edge_counts.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert_eq!(
+            serde_json::to_string(&decomposition.powergraph()).unwrap(),
+            serde_json::to_string(&decomposed).unwrap()
+        );
+    }
+
+    #[test]
+    fn candidates_from_adjacent_clusters() {
+        for options in [
+            DecompositionOptions::default(),
+            DecompositionOptions {
+                directed: true,
+                max_missing_edges: 1,
+                ..Default::default()
+            },
+        ] {
+            let mut graph = powergraph();
+            let mut state = DecompositionState::new();
+            while state.phase != Phase::PowerEdges {
+                state
+                    .step(
+                        &mut graph,
+                        &options,
+                        &options.similarity,
+                        &mut NoProgress,
+                        1,
+                    )
+                    .unwrap();
+            }
+            let candidates = std::mem::take(&mut state.queued_candidates);
+
+            // Pairing every cluster with every other finds no more candidates.
+            let count = state.cluster_keys.len();
+            let all_pairs: HashSet<PowerEdgeCandidate> = (0..count)
+                .flat_map(|one| (one..count).map(move |two| (one, two)))
+                .flat_map(|(one, two)| state.pair(&graph, &options, one, two))
+                .collect();
+
+            assert!(!candidates.is_empty());
+            assert_eq!(candidates, all_pairs);
+        }
+    }
+
+    #[test]
+    fn step_without_work() {
+        let mut decomposition =
+            Decomposition::new(powergraph(), &DecompositionOptions::default()).unwrap();
+
+        assert!(!decomposition.step(0));
+        assert_eq!(decomposition.phase(), "singletons");
+        assert_eq!(decomposition.powergraph().power_node_count(), 0);
+    }
+}
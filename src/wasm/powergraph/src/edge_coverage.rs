@@ -0,0 +1,69 @@
+//! An index from the edges of a graph to the power edges whose nodes they
+//! join, so that the power edges overlapping a candidate are found without
+//! expanding every power edge.
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::interner::NodeIndex;
+
+#[derive(Debug, Clone, Default)]
+pub struct EdgeCoverage {
+    /// The power edges, by index into `PowerGraph::power_edges`, whose nodes
+    /// are joined by each edge.
+    covering: HashMap<(NodeIndex, NodeIndex), Vec<usize>>,
+    /// The number of edges joining the nodes of each power edge.
+    sizes: HashMap<usize, usize>,
+}
+
+impl EdgeCoverage {
+    pub fn new() -> EdgeCoverage {
+        EdgeCoverage::default()
+    }
+
+    /// Record the edges joining the nodes of the power edge at `index`.
+    pub fn add(&mut self, index: usize, edges: Vec<(NodeIndex, NodeIndex)>) {
+        self.sizes.insert(index, edges.len());
+        for edge in edges {
+            self.covering.entry(edge).or_default().push(index);
+        }
+    }
+
+    /// The power edges sharing any of `edges`, by index, with the number of
+    /// `edges` each shares.
+    pub fn overlapping(&self, edges: &[(NodeIndex, NodeIndex)]) -> BTreeMap<usize, usize> {
+        let mut overlapping = BTreeMap::new();
+        for power_edge in edges
+            .iter()
+            .filter_map(|edge| self.covering.get(edge))
+            .flatten()
+        {
+            *overlapping.entry(*power_edge).or_default() += 1;
+        }
+        overlapping
+    }
+
+    /// The number of edges joining the nodes of the power edge at `index`.
+    pub fn size(&self, index: usize) -> usize {
+        self.sizes.get(&index).copied().unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overlapping_power_edges() {
+        let mut coverage = EdgeCoverage::new();
+        coverage.add(0, vec![(0, 1), (0, 2)]);
+        coverage.add(1, vec![(0, 2), (1, 3), (2, 3)]);
+        coverage.add(2, vec![(4, 5)]);
+
+        assert_eq!(
+            coverage.overlapping(&[(0, 2), (2, 3), (3, 4)]),
+            BTreeMap::from([(0, 1), (1, 2)])
+        );
+        assert!(coverage.overlapping(&[(5, 4)]).is_empty());
+        assert_eq!(coverage.size(1), 3);
+    }
+}
@@ -1,13 +1,23 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet};
 
 use serde::Serialize;
 
-use crate::{sets::Set, Edge};
+use crate::{
+    interner::{Interner, NodeIndex},
+    node_set::NodeSet,
+    Edge,
+};
 
 #[derive(Serialize, Clone)]
 pub struct EdgeRepository {
-    child_map: HashMap<String, HashSet<String>>,
-    parent_map: HashMap<String, HashSet<String>>,
+    child_map: BTreeMap<String, BTreeSet<String>>,
+    parent_map: BTreeMap<String, BTreeSet<String>>,
+    /// The children of each node, by interned index.
+    #[serde(skip)]
+    children: Vec<NodeSet>,
+    /// The parents of each node, by interned index.
+    #[serde(skip)]
+    parents: Vec<NodeSet>,
 }
 
 impl IntoIterator for EdgeRepository {
@@ -31,105 +41,103 @@ impl IntoIterator for EdgeRepository {
 
 impl EdgeRepository {
     pub fn new() -> EdgeRepository {
-        let child_map = HashMap::new();
-        let parent_map = HashMap::new();
+        let child_map = BTreeMap::new();
+        let parent_map = BTreeMap::new();
         EdgeRepository {
             child_map,
             parent_map,
+            children: Vec::new(),
+            parents: Vec::new(),
         }
     }
 
-    pub fn from_edge_list(edges: Vec<Edge>) -> EdgeRepository {
+    /// Index the edges by the ids of their nodes, and by the indices of any
+    /// nodes interned in `interner`.
+    pub fn from_edge_list(edges: Vec<Edge>, interner: &Interner) -> EdgeRepository {
         let mut edge_repository = EdgeRepository::new();
+        edge_repository.children = vec![NodeSet::new(); interner.len()];
+        edge_repository.parents = vec![NodeSet::new(); interner.len()];
 
         for edge in edges {
-            edge_repository.add_edge(&edge)
+            edge_repository.add_edge(&edge, interner)
         }
 
-        return edge_repository;
+        edge_repository
     }
 
-    pub fn add_edge(&mut self, edge: &Edge) {
-        let from_set = self
-            .child_map
-            .entry(edge.from.clone())
-            .or_insert(HashSet::new());
+    pub fn add_edge(&mut self, edge: &Edge, interner: &Interner) {
+        let from_set = self.child_map.entry(edge.from.clone()).or_default();
         from_set.insert(edge.to.clone());
 
-        let to_set = self
-            .parent_map
-            .entry(edge.to.clone())
-            .or_insert(HashSet::new());
+        let to_set = self.parent_map.entry(edge.to.clone()).or_default();
         to_set.insert(edge.from.clone());
-    }
 
-    pub fn get_edge(&self, from: &String, to: &String) -> Option<Edge> {
-        match self.child_map.get(from) {
-            Some(targets) => match targets.get(to) {
-                Some(_) => {
-                    return Some(Edge {
-                        from: from.clone(),
-                        to: to.clone(),
-                    })
-                }
-                None => return None,
-            },
-            None => return None,
+        if let (Some(from), Some(to)) = (interner.index(&edge.from), interner.index(&edge.to)) {
+            self.children[from as usize].insert(to);
+            self.parents[to as usize].insert(from);
         }
     }
 
-    pub fn subgraph(&self, nodes: &Set<String>) -> Vec<Edge> {
+    pub fn has_edge(&self, from: NodeIndex, to: NodeIndex) -> bool {
+        self.children[from as usize].contains(to)
+    }
+
+    /// The edges between `nodes`, by the indices of their nodes.
+    pub fn subgraph(&self, nodes: &NodeSet) -> Vec<(NodeIndex, NodeIndex)> {
         nodes
             .iter()
-            .filter_map(
-                |source_node| match self.child_map.get(source_node) {
-                    Some(targets) => Some((source_node, targets)),
-                    None => None,
-                },
-            )
-            .flat_map(|(source, target_set)| {
-                target_set
+            .flat_map(|source| {
+                self.children(source)
+                    .intersection(nodes)
                     .iter()
-                    .filter(|target| nodes.items.contains(*target))
-                    .map(move |target| Edge {
-                        from: source.clone(),
-                        to: target.clone(),
-                    })
+                    .map(|target| (source, target))
+                    .collect::<Vec<(NodeIndex, NodeIndex)>>()
             })
             .collect()
     }
 
-    pub fn parents(&self, node: &String) -> Set<String> {
-        match self.parent_map.get(node) {
-            Some(parents) => {
-                return Set::from_set(parents.clone());
-            }
-            None => {
-                return Set::new();
-            }
-        }
+    pub fn parents(&self, node: NodeIndex) -> &NodeSet {
+        &self.parents[node as usize]
+    }
+
+    pub fn children(&self, node: NodeIndex) -> &NodeSet {
+        &self.children[node as usize]
+    }
+
+    pub fn len(&self) -> usize {
+        self.child_map.values().map(|targets| targets.len()).sum()
     }
 
-    pub fn children(&self, node: &String) -> Set<String> {
-        match self.child_map.get(node) {
-            Some(children) => {
-                return Set::from_set(children.clone());
+    /// The connected components of the graph, ignoring the direction of its
+    /// edges, by the indices of their nodes and in order of their lowest node.
+    pub fn components(&self) -> Vec<NodeSet> {
+        let mut visited = NodeSet::new();
+        let mut components = vec![];
+        for start in 0..self.children.len() as NodeIndex {
+            if visited.contains(start) {
+                continue;
             }
-            None => {
-                return Set::new();
+            visited.insert(start);
+
+            let mut component = NodeSet::single(start);
+            let mut frontier = vec![start];
+            while let Some(node) = frontier.pop() {
+                for neighbor in self.parents(node).union(self.children(node)).iter() {
+                    if !visited.contains(neighbor) {
+                        visited.insert(neighbor);
+                        component.insert(neighbor);
+                        frontier.push(neighbor);
+                    }
+                }
             }
+            components.push(component);
         }
-    }
-
-    pub fn len(self) -> usize {
-        self.into_iter().count()
+        components
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use itertools::Itertools;
-
     use super::*;
 
     #[test]
@@ -151,20 +159,53 @@ mod tests {
             Edge::new("x", "z"),
             Edge::new("t", "z"),
         ];
-        let edge_repository = EdgeRepository::from_edge_list(edges);
-
-        let search_nodes = Set::from_iter(vec!["s".to_string(), "t".to_string(), "y".to_string()]);
+        let interner = Interner::new(
+            edges
+                .iter()
+                .flat_map(|edge| [edge.from.as_str(), edge.to.as_str()]),
+        );
+        let edge_repository = EdgeRepository::from_edge_list(edges, &interner);
 
-        let subgraph = edge_repository.subgraph(&search_nodes);
+        let search_nodes = interner.set(["s", "t", "y"]);
 
-        let subgraph_sorted: Vec<Edge> = subgraph
+        let subgraph: Vec<Edge> = edge_repository
+            .subgraph(&search_nodes)
             .into_iter()
-            .sorted_by(|a, b| Ord::cmp(&a.get_id(), &b.get_id()))
+            .map(|(from, to)| Edge::new(interner.name(from), interner.name(to)))
             .collect();
 
+        assert_eq!(subgraph, vec![Edge::new("y", "s"), Edge::new("y", "t"),]);
+        assert!(
+            edge_repository.has_edge(interner.index("x").unwrap(), interner.index("t").unwrap())
+        );
+        assert!(
+            !edge_repository.has_edge(interner.index("t").unwrap(), interner.index("x").unwrap())
+        );
+    }
+
+    #[test]
+    fn connected_components() {
+        let edges: Vec<Edge> = vec![
+            Edge::new("a", "b"),
+            Edge::new("c", "b"),
+            Edge::new("d", "e"),
+        ];
+        let interner = Interner::new(
+            ["a", "b", "c", "d", "e", "f"].into_iter().chain(
+                edges
+                    .iter()
+                    .flat_map(|edge| [edge.from.as_str(), edge.to.as_str()]),
+            ),
+        );
+        let edge_repository = EdgeRepository::from_edge_list(edges, &interner);
+
         assert_eq!(
-            subgraph_sorted,
-            vec![Edge::new("y", "s"), Edge::new("y", "t"),]
-        )
+            edge_repository.components(),
+            vec![
+                interner.set(["a", "b", "c"]),
+                interner.set(["d", "e"]),
+                interner.set(["f"]),
+            ]
+        );
     }
 }
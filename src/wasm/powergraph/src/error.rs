@@ -0,0 +1,82 @@
+use std::fmt::Display;
+
+/// Errors raised while loading a graph into a `PowerGraph`.
+#[derive(Debug)]
+pub enum PowerGraphError {
+    /// The input could not be read.
+    Io(std::io::Error),
+    /// The input was read, but is not valid JSON for the expected schema.
+    Parse(serde_json::Error),
+    /// An edge references a node that is not defined anywhere in the input.
+    UnknownNode { id: String, referenced_by: String },
+    /// A node selection expression could not be parsed.
+    InvalidSelector(String),
+    /// The input is not valid for the given graph format.
+    Syntax {
+        format: &'static str,
+        line: Option<usize>,
+        message: String,
+    },
+    /// A set of manifests cannot be merged into a single dbt Mesh graph.
+    InvalidMesh(String),
+    /// The decomposition options are out of range.
+    InvalidOptions(String),
+    /// The decomposition was cancelled before it finished.
+    Cancelled,
+}
+
+impl Display for PowerGraphError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PowerGraphError::Io(error) => write!(f, "{}", error),
+            PowerGraphError::Parse(error) => write!(f, "malformed JSON: {}", error),
+            PowerGraphError::UnknownNode { id, referenced_by } => {
+                write!(f, "unknown node `{}` referenced by `{}`", id, referenced_by)
+            }
+            PowerGraphError::InvalidSelector(message) => write!(f, "{}", message),
+            PowerGraphError::InvalidMesh(message) => write!(f, "{}", message),
+            PowerGraphError::InvalidOptions(message) => write!(f, "{}", message),
+            PowerGraphError::Cancelled => write!(f, "cancelled"),
+            PowerGraphError::Syntax {
+                format,
+                line: Some(line),
+                message,
+            } => write!(f, "invalid {} on line {}: {}", format, line, message),
+            PowerGraphError::Syntax {
+                format,
+                line: None,
+                message,
+            } => write!(f, "invalid {}: {}", format, message),
+        }
+    }
+}
+
+impl std::error::Error for PowerGraphError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PowerGraphError::Io(error) => Some(error),
+            PowerGraphError::Parse(error) => Some(error),
+            PowerGraphError::UnknownNode { .. }
+            | PowerGraphError::InvalidSelector(_)
+            | PowerGraphError::Syntax { .. }
+            | PowerGraphError::InvalidMesh(_)
+            | PowerGraphError::InvalidOptions(_)
+            | PowerGraphError::Cancelled => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for PowerGraphError {
+    fn from(error: std::io::Error) -> Self {
+        PowerGraphError::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for PowerGraphError {
+    fn from(error: serde_json::Error) -> Self {
+        match error.classify() {
+            serde_json::error::Category::Io => PowerGraphError::Io(error.into()),
+            _ => PowerGraphError::Parse(error),
+        }
+    }
+}
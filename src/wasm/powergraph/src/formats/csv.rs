@@ -0,0 +1,161 @@
+//! Plain edge lists, one `from,to` pair per line. A header row such as
+//! `source,target` is skipped, extra columns are ignored, and a line with a
+//! single field declares an isolated node. Lines starting with `#` are comments.
+
+use super::{dedup_edges, dedup_nodes, syntax_error, with_implicit_nodes};
+use crate::{error::PowerGraphError, Edge, Node};
+
+const FROM_HEADERS: [&str; 4] = ["source", "from", "parent", "upstream"];
+const TO_HEADERS: [&str; 4] = ["target", "to", "child", "downstream"];
+
+/// Read a comma separated edge list.
+pub fn read_csv(input: &str) -> Result<(Vec<Node>, Vec<Edge>), PowerGraphError> {
+    read(input, ',', "CSV")
+}
+
+/// Read a tab separated edge list.
+pub fn read_tsv(input: &str) -> Result<(Vec<Node>, Vec<Edge>), PowerGraphError> {
+    read(input, '\t', "TSV")
+}
+
+fn read(
+    input: &str,
+    delimiter: char,
+    format: &'static str,
+) -> Result<(Vec<Node>, Vec<Edge>), PowerGraphError> {
+    let mut nodes: Vec<Node> = Vec::new();
+    let mut edges: Vec<Edge> = Vec::new();
+    let mut first_record = true;
+
+    for (index, line) in input.lines().enumerate() {
+        let line = line.trim_end_matches('\r');
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let fields = split_record(line, delimiter)
+            .map_err(|message| syntax_error(format, Some(index + 1), message))?;
+
+        if first_record {
+            first_record = false;
+            if is_header(&fields) {
+                continue;
+            }
+        }
+
+        match fields.as_slice() {
+            [id] if !id.is_empty() => nodes.push(Node::new(id.clone(), "{}".to_string())),
+            [from, to, ..] if !from.is_empty() && !to.is_empty() => edges.push(Edge::new(from, to)),
+            _ => {
+                return Err(syntax_error(
+                    format,
+                    Some(index + 1),
+                    "expected a node id or a pair of node ids".to_string(),
+                ))
+            }
+        }
+    }
+
+    let edges = dedup_edges(edges);
+    Ok((with_implicit_nodes(dedup_nodes(nodes), &edges), edges))
+}
+
+fn is_header(fields: &[String]) -> bool {
+    match fields {
+        [from, to, ..] => {
+            FROM_HEADERS.contains(&from.to_lowercase().as_str())
+                && TO_HEADERS.contains(&to.to_lowercase().as_str())
+        }
+        _ => false,
+    }
+}
+
+/// Split a single record, honouring double-quoted fields with `""` escapes.
+fn split_record(line: &str, delimiter: char) -> Result<Vec<String>, String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' if in_quotes => in_quotes = false,
+            '"' if field.trim().is_empty() => {
+                field.clear();
+                in_quotes = true;
+            }
+            c if c == delimiter && !in_quotes => {
+                fields.push(field.trim().to_string());
+                field.clear();
+            }
+            c => field.push(c),
+        }
+    }
+
+    if in_quotes {
+        return Err("unterminated quoted field".to_string());
+    }
+
+    fields.push(field.trim().to_string());
+    Ok(fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_csv_edge_list() {
+        let input = "source,target,weight\n\
+                     # Airflow task dependencies\n\
+                     extract,transform,1\n\
+                     \"transform\",\"load, final\",2\n\
+                     extract,transform\n\
+                     orphan\n";
+
+        let (nodes, edges) = read_csv(input).unwrap();
+
+        assert_eq!(
+            edges,
+            vec![
+                Edge::new("extract", "transform"),
+                Edge::new("transform", "load, final"),
+            ]
+        );
+        let ids: Vec<&str> = nodes.iter().map(|node| node.id.as_str()).collect();
+        assert_eq!(ids, vec!["orphan", "extract", "transform", "load, final"]);
+    }
+
+    #[test]
+    fn read_csv_repeated_nodes() {
+        let (nodes, edges) = read_csv("x\nx\na,b\na\n").unwrap();
+
+        let ids: Vec<&str> = nodes.iter().map(|node| node.id.as_str()).collect();
+        assert_eq!(ids, vec!["x", "a", "b"]);
+        assert_eq!(edges, vec![Edge::new("a", "b")]);
+    }
+
+    #[test]
+    fn read_tsv_without_header() {
+        let (nodes, edges) = read_tsv("a\tb\nb\tc\n").unwrap();
+
+        assert_eq!(nodes.len(), 3);
+        assert_eq!(edges, vec![Edge::new("a", "b"), Edge::new("b", "c")]);
+    }
+
+    #[test]
+    fn read_csv_errors() {
+        assert!(matches!(
+            read_csv("a,b\n\"c,d\n"),
+            Err(PowerGraphError::Syntax { line: Some(2), .. })
+        ));
+        assert!(matches!(
+            read_csv("a,\n"),
+            Err(PowerGraphError::Syntax { line: Some(1), .. })
+        ));
+    }
+}
@@ -0,0 +1,126 @@
+//! Cytoscape.js elements JSON. Power nodes become compound nodes that are the
+//! `parent` of their members, and node data is carried into each element's
+//! `data`.
+
+use serde_json::{json, Map, Value};
+
+use super::{node_attributes, unique_power_edges, Nesting};
+use crate::PowerGraph;
+
+/// Write a decomposed power graph as Cytoscape.js elements. Each power node
+/// with more than one member is a compound node nested inside the smallest
+/// power node containing it, and every edge has a `kind` of `power` or
+/// `residual` for plain edges left over after decomposition. Power edges from
+/// a lossy decomposition list the edges they do not imply as `negative_edges`.
+pub fn write_cytoscape(powergraph: &PowerGraph) -> Value {
+    let nesting = Nesting::new(powergraph);
+
+    let groups = nesting.groups.iter().enumerate().map(|(index, group)| {
+        let mut data = Map::new();
+        data.insert("id".to_string(), json!(Nesting::group_id(index)));
+        data.insert("label".to_string(), json!(group.id));
+        data.insert("power_node".to_string(), json!(true));
+        if let Some(parent) = nesting.group_parents[index] {
+            data.insert("parent".to_string(), json!(Nesting::group_id(parent)));
+        }
+        json!({ "data": data })
+    });
+
+    let nodes = powergraph.nodes.iter().map(|node| {
+        let mut data = node_attributes(node);
+        data.insert("id".to_string(), json!(node.id));
+        match nesting.node_parents.get(node.id.as_str()) {
+            Some(&parent) => {
+                data.insert("parent".to_string(), json!(Nesting::group_id(parent)));
+            }
+            None => {
+                data.remove("parent");
+            }
+        }
+        json!({ "data": data })
+    });
+
+    let edges =
+        unique_power_edges(powergraph)
+            .into_iter()
+            .enumerate()
+            .map(|(index, power_edge)| {
+                let kind = if power_edge.residual {
+                    "residual"
+                } else {
+                    "power"
+                };
+                let mut data = json!({
+                    "id": format!("edge_{}", index),
+                    "source": nesting.endpoint_id(&power_edge.from),
+                    "target": nesting.endpoint_id(&power_edge.to),
+                    "kind": kind,
+                });
+                if !power_edge.negative_edges.is_empty() {
+                    data["negative_edges"] = power_edge
+                        .negative_edges
+                        .iter()
+                        .map(|edge| json!({"source": edge.from, "target": edge.to}))
+                        .collect();
+                }
+                json!({ "data": data })
+            });
+
+    json!({
+        "elements": {
+            "nodes": groups.chain(nodes).collect::<Vec<Value>>(),
+            "edges": edges.collect::<Vec<Value>>(),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_cytoscape_compound_nodes() {
+        let powergraph = crate::formats::tests::nested_powergraph();
+
+        let elements = write_cytoscape(&powergraph);
+
+        assert_eq!(
+            elements["elements"]["nodes"],
+            json!([
+                {"data": {"id": "power_node_0", "label": "a-b-c", "power_node": true}},
+                {"data": {"id": "power_node_1", "label": "a-b", "power_node": true, "parent": "power_node_0"}},
+                {"data": {"id": "power_node_2", "label": "d-e", "power_node": true}},
+                {"data": {"id": "a", "name": "a", "tags": ["x"], "rows": 3, "parent": "power_node_1"}},
+                {"data": {"id": "b", "parent": "power_node_1"}},
+                {"data": {"id": "c", "parent": "power_node_0"}},
+                {"data": {"id": "d", "parent": "power_node_2"}},
+                {"data": {"id": "e", "parent": "power_node_2"}},
+                {"data": {"id": "say \"hi\""}},
+            ])
+        );
+        assert_eq!(
+            elements["elements"]["edges"],
+            json!([
+                {"data": {"id": "edge_0", "source": "power_node_0", "target": "power_node_0", "kind": "power"}},
+                {"data": {"id": "edge_1", "source": "power_node_1", "target": "power_node_2", "kind": "power"}},
+                {"data": {"id": "edge_2", "source": "c", "target": "say \"hi\"", "kind": "residual"}},
+            ])
+        );
+    }
+
+    #[test]
+    fn write_cytoscape_negative_edges() {
+        let mut powergraph = crate::formats::tests::nested_powergraph();
+        powergraph.power_edges[1].negative_edges = vec![crate::Edge::new("b", "e")];
+
+        let elements = write_cytoscape(&powergraph);
+
+        assert_eq!(
+            elements["elements"]["edges"][1]["data"]["negative_edges"],
+            json!([{"source": "b", "target": "e"}])
+        );
+        assert!(elements["elements"]["edges"][0]["data"]
+            .get("negative_edges")
+            .is_none());
+    }
+}
@@ -0,0 +1,695 @@
+//! Graphviz DOT. When reading, nodes inside subgraphs are flattened into the
+//! graph, edge statements between subgraphs connect every node on either side,
+//! and node attributes are carried into `Node.data` as a JSON object. When
+//! writing, power nodes become nested `subgraph cluster_*` blocks.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Write;
+
+use super::{dedup_edges, syntax_error, unique_power_edges, Nesting};
+use crate::{error::PowerGraphError, Edge, Node, NodeAttributes, PowerGraph};
+
+const FORMAT: &str = "DOT";
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Id(String),
+    LeftBrace,
+    RightBrace,
+    LeftBracket,
+    RightBracket,
+    Semicolon,
+    Comma,
+    Equals,
+    Colon,
+    EdgeOp,
+}
+
+struct Lexer<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+    line: usize,
+}
+
+impl Lexer<'_> {
+    fn tokenize(input: &str) -> Result<Vec<(Token, usize)>, PowerGraphError> {
+        let mut lexer = Lexer {
+            chars: input.chars().peekable(),
+            line: 1,
+        };
+        let mut tokens = Vec::new();
+
+        while let Some(token) = lexer.next_token()? {
+            tokens.push((token, lexer.line));
+        }
+
+        Ok(tokens)
+    }
+
+    fn error(&self, message: &str) -> PowerGraphError {
+        syntax_error(FORMAT, Some(self.line), message.to_string())
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.chars.next();
+        if c == Some('\n') {
+            self.line += 1;
+        }
+        c
+    }
+
+    fn skip_line(&mut self) {
+        while let Some(c) = self.chars.peek() {
+            if *c == '\n' {
+                break;
+            }
+            self.bump();
+        }
+    }
+
+    fn next_token(&mut self) -> Result<Option<Token>, PowerGraphError> {
+        loop {
+            let c = match self.chars.peek() {
+                Some(c) => *c,
+                None => return Ok(None),
+            };
+
+            if c.is_whitespace() {
+                self.bump();
+                continue;
+            }
+
+            if c == '#' {
+                self.skip_line();
+                continue;
+            }
+
+            if c == '/' {
+                self.bump();
+                match self.bump() {
+                    Some('/') => self.skip_line(),
+                    Some('*') => {
+                        let mut previous = ' ';
+                        loop {
+                            match self.bump() {
+                                Some('/') if previous == '*' => break,
+                                Some(c) => previous = c,
+                                None => return Err(self.error("unterminated comment")),
+                            }
+                        }
+                    }
+                    _ => return Err(self.error("unexpected `/`")),
+                }
+                continue;
+            }
+
+            let token = match c {
+                '{' => Token::LeftBrace,
+                '}' => Token::RightBrace,
+                '[' => Token::LeftBracket,
+                ']' => Token::RightBracket,
+                ';' => Token::Semicolon,
+                ',' => Token::Comma,
+                '=' => Token::Equals,
+                ':' => Token::Colon,
+                '"' => return self.quoted().map(Some),
+                '<' => return self.html().map(Some),
+                '-' => {
+                    self.bump();
+                    match self.chars.peek() {
+                        Some('>') | Some('-') => {
+                            self.bump();
+                            return Ok(Some(Token::EdgeOp));
+                        }
+                        _ => return self.identifier(String::from("-")).map(Some),
+                    }
+                }
+                _ => return self.identifier(String::new()).map(Some),
+            };
+
+            self.bump();
+            return Ok(Some(token));
+        }
+    }
+
+    fn quoted(&mut self) -> Result<Token, PowerGraphError> {
+        self.bump();
+        let mut value = String::new();
+
+        loop {
+            match self.bump() {
+                Some('\\') => match self.bump() {
+                    Some('"') => value.push('"'),
+                    Some('\n') => {}
+                    Some(c) => {
+                        value.push('\\');
+                        value.push(c);
+                    }
+                    None => return Err(self.error("unterminated string")),
+                },
+                Some('"') => return Ok(Token::Id(value)),
+                Some(c) => value.push(c),
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+    }
+
+    /// An HTML-like label, keeping the markup without the outer angle brackets.
+    fn html(&mut self) -> Result<Token, PowerGraphError> {
+        self.bump();
+        let mut value = String::new();
+        let mut depth = 1;
+
+        loop {
+            match self.bump() {
+                Some('<') => {
+                    depth += 1;
+                    value.push('<');
+                }
+                Some('>') => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Ok(Token::Id(value));
+                    }
+                    value.push('>');
+                }
+                Some(c) => value.push(c),
+                None => return Err(self.error("unterminated HTML string")),
+            }
+        }
+    }
+
+    fn identifier(&mut self, mut value: String) -> Result<Token, PowerGraphError> {
+        while let Some(c) = self.chars.peek() {
+            if c.is_alphanumeric() || *c == '_' || *c == '.' {
+                value.push(*c);
+                self.bump();
+            } else {
+                break;
+            }
+        }
+
+        if value.is_empty() || value == "-" {
+            let unexpected = self.chars.peek().copied().unwrap_or(' ');
+            return Err(self.error(&format!("unexpected character `{}`", unexpected)));
+        }
+
+        Ok(Token::Id(value))
+    }
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    position: usize,
+    node_order: Vec<String>,
+    node_attributes: HashMap<String, BTreeMap<String, String>>,
+    edges: Vec<Edge>,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(token, _)| token)
+    }
+
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Id(id)) if id.eq_ignore_ascii_case(keyword))
+    }
+
+    fn line(&self) -> Option<usize> {
+        self.tokens
+            .get(self.position)
+            .or(self.tokens.last())
+            .map(|(_, line)| *line)
+    }
+
+    fn error(&self, message: &str) -> PowerGraphError {
+        syntax_error(FORMAT, self.line(), message.to_string())
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self
+            .tokens
+            .get(self.position)
+            .map(|(token, _)| token.clone());
+        self.position += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), PowerGraphError> {
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            Some(token) => Err(self.error(&format!("expected {:?}, found {:?}", expected, token))),
+            None => Err(self.error(&format!("expected {:?}, found end of input", expected))),
+        }
+    }
+
+    fn identifier(&mut self) -> Result<String, PowerGraphError> {
+        match self.next() {
+            Some(Token::Id(id)) => Ok(id),
+            Some(token) => Err(self.error(&format!("expected an identifier, found {:?}", token))),
+            None => Err(self.error("expected an identifier, found end of input")),
+        }
+    }
+
+    fn declare_node(&mut self, id: &str) {
+        if !self.node_attributes.contains_key(id) {
+            self.node_order.push(id.to_string());
+            self.node_attributes.insert(id.to_string(), BTreeMap::new());
+        }
+    }
+
+    fn graph(&mut self) -> Result<(), PowerGraphError> {
+        if self.peek_keyword("strict") {
+            self.next();
+        }
+
+        if !(self.peek_keyword("graph") || self.peek_keyword("digraph")) {
+            return Err(self.error("expected `graph` or `digraph`"));
+        }
+        self.next();
+
+        if let Some(Token::Id(_)) = self.peek() {
+            self.next();
+        }
+
+        self.expect(Token::LeftBrace)?;
+        self.statements()?;
+
+        if self.next().is_some() {
+            return Err(self.error("unexpected input after the closing `}`"));
+        }
+
+        Ok(())
+    }
+
+    /// Parse statements up to and including the closing brace, returning the
+    /// ids of every node declared within them.
+    fn statements(&mut self) -> Result<Vec<String>, PowerGraphError> {
+        let mut declared: Vec<String> = Vec::new();
+
+        loop {
+            match self.peek() {
+                Some(Token::RightBrace) => {
+                    self.next();
+                    return Ok(declared);
+                }
+                Some(Token::Semicolon) => {
+                    self.next();
+                }
+                Some(_) => declared.extend(self.statement()?),
+                None => return Err(self.error("expected `}`, found end of input")),
+            }
+        }
+    }
+
+    fn statement(&mut self) -> Result<Vec<String>, PowerGraphError> {
+        if self.peek_keyword("graph") || self.peek_keyword("node") || self.peek_keyword("edge") {
+            self.next();
+            self.attributes()?;
+            return Ok(vec![]);
+        }
+
+        if let (Some(Token::Id(_)), Some((Token::Equals, _))) =
+            (self.peek(), self.tokens.get(self.position + 1))
+        {
+            self.next();
+            self.next();
+            self.identifier()?;
+            return Ok(vec![]);
+        }
+
+        let mut operands = vec![self.operand()?];
+        while let Some(Token::EdgeOp) = self.peek() {
+            self.next();
+            operands.push(self.operand()?);
+        }
+
+        let attributes = self.attributes()?;
+
+        if operands.len() == 1 {
+            let operand = operands.remove(0);
+            if let [id] = operand.as_slice() {
+                if let Some(node_attributes) = self.node_attributes.get_mut(id) {
+                    node_attributes.extend(attributes);
+                }
+            }
+            return Ok(operand);
+        }
+
+        for pair in operands.windows(2) {
+            for from in pair[0].iter() {
+                for to in pair[1].iter() {
+                    self.edges.push(Edge::new(from, to));
+                }
+            }
+        }
+
+        Ok(operands.into_iter().flatten().collect())
+    }
+
+    /// A node id or a subgraph, returning the node ids it refers to.
+    fn operand(&mut self) -> Result<Vec<String>, PowerGraphError> {
+        if self.peek_keyword("subgraph") {
+            self.next();
+            if let Some(Token::Id(_)) = self.peek() {
+                self.next();
+            }
+        }
+
+        if let Some(Token::LeftBrace) = self.peek() {
+            self.next();
+            return self.statements();
+        }
+
+        let id = self.identifier()?;
+
+        // Ports are not part of the node id.
+        while let Some(Token::Colon) = self.peek() {
+            self.next();
+            self.identifier()?;
+        }
+
+        self.declare_node(&id);
+        Ok(vec![id])
+    }
+
+    fn attributes(&mut self) -> Result<BTreeMap<String, String>, PowerGraphError> {
+        let mut attributes = BTreeMap::new();
+
+        while let Some(Token::LeftBracket) = self.peek() {
+            self.next();
+            loop {
+                match self.peek() {
+                    Some(Token::RightBracket) => {
+                        self.next();
+                        break;
+                    }
+                    Some(Token::Comma) | Some(Token::Semicolon) => {
+                        self.next();
+                    }
+                    _ => {
+                        let key = self.identifier()?;
+                        self.expect(Token::Equals)?;
+                        let value = self.identifier()?;
+                        attributes.insert(key, value);
+                    }
+                }
+            }
+        }
+
+        Ok(attributes)
+    }
+}
+
+/// Read a Graphviz DOT graph or digraph.
+pub fn read_dot(input: &str) -> Result<(Vec<Node>, Vec<Edge>), PowerGraphError> {
+    let mut parser = Parser {
+        tokens: Lexer::tokenize(input)?,
+        position: 0,
+        node_order: Vec::new(),
+        node_attributes: HashMap::new(),
+        edges: Vec::new(),
+    };
+    parser.graph()?;
+
+    let nodes = parser
+        .node_order
+        .iter()
+        .map(|id| {
+            let attributes = &parser.node_attributes[id];
+            let data = serde_json::to_string(attributes)?;
+            Ok(Node::new(id.clone(), data).with_attributes(NodeAttributes::from_pairs(attributes)))
+        })
+        .collect::<Result<Vec<Node>, PowerGraphError>>()?;
+
+    Ok((nodes, dedup_edges(parser.edges)))
+}
+
+/// Quote and escape a DOT identifier.
+fn quote(id: &str) -> String {
+    format!("\"{}\"", id.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn cluster_name(group: usize) -> String {
+    format!("cluster_{}", group)
+}
+
+struct DotWriter<'a> {
+    powergraph: &'a PowerGraph,
+    nesting: Nesting<'a>,
+    /// Groups with a power edge to themselves, drawn with a bold border.
+    cliques: HashSet<usize>,
+    output: String,
+}
+
+impl DotWriter<'_> {
+    fn line(&mut self, depth: usize, line: &str) {
+        let _ = writeln!(self.output, "{}{}", "  ".repeat(depth), line);
+    }
+
+    fn group(&mut self, group: usize, depth: usize) {
+        self.line(depth, &format!("subgraph {} {{", cluster_name(group)));
+        self.line(
+            depth + 1,
+            &format!("tooltip={};", quote(&self.nesting.groups[group].id)),
+        );
+        self.line(depth + 1, "label=\"\";");
+        if self.cliques.contains(&group) {
+            self.line(depth + 1, "style=bold;");
+        }
+
+        for child in 0..self.nesting.groups.len() {
+            if self.nesting.group_parents[child] == Some(group) {
+                self.group(child, depth + 1);
+            }
+        }
+        self.nodes(Some(group), depth + 1);
+
+        self.line(depth, "}");
+    }
+
+    fn nodes(&mut self, group: Option<usize>, depth: usize) {
+        let nodes: Vec<String> = self
+            .powergraph
+            .nodes
+            .iter()
+            .filter(|node| self.nesting.node_parents.get(node.id.as_str()).copied() == group)
+            .map(|node| format!("{};", quote(&node.id)))
+            .collect();
+
+        for node in nodes {
+            self.line(depth, &node);
+        }
+    }
+
+    /// The node an edge is drawn to for a power edge endpoint, and the cluster
+    /// the edge is clipped to if the endpoint is a group.
+    fn endpoint(&self, id: &str) -> (String, Option<String>) {
+        match self.nesting.group_index.get(id) {
+            Some(&group) => (
+                quote(self.nesting.members(group)[0]),
+                Some(cluster_name(group)),
+            ),
+            None => (quote(id), None),
+        }
+    }
+}
+
+/// Write a decomposed power graph as a Graphviz digraph. Each power node is a
+/// `subgraph cluster_*` nested inside the smallest power node containing it,
+/// power edges between power nodes are clipped to their clusters with
+/// `ltail`/`lhead`, and singletons remain plain nodes. A power edge from a
+/// power node to itself marks a clique, which is drawn as a bold cluster
+/// border because Graphviz cannot clip both ends of an edge to one cluster.
+/// The ends of a power edge are in no particular order unless the
+/// decomposition was directed, so edges are drawn without arrowheads. Edges
+/// that a power edge from a lossy decomposition implies but that are not in
+/// the graph are drawn dashed and red.
+pub fn write_dot(powergraph: &PowerGraph) -> String {
+    let nesting = Nesting::new(powergraph);
+    let power_edges = unique_power_edges(powergraph);
+
+    let cliques = power_edges
+        .iter()
+        .filter(|power_edge| power_edge.from == power_edge.to)
+        .filter_map(|power_edge| nesting.group_index.get(power_edge.from.as_str()).copied())
+        .collect();
+
+    let mut writer = DotWriter {
+        powergraph,
+        nesting,
+        cliques,
+        output: String::new(),
+    };
+
+    writer.line(0, "digraph powergraph {");
+    writer.line(1, "compound=true;");
+    writer.line(1, "node [shape=box];");
+    if !powergraph.directed {
+        writer.line(1, "edge [dir=none];");
+    }
+
+    for group in 0..writer.nesting.groups.len() {
+        if writer.nesting.group_parents[group].is_none() {
+            writer.group(group, 1);
+        }
+    }
+    writer.nodes(None, 1);
+
+    for power_edge in power_edges.iter() {
+        let (from, ltail) = writer.endpoint(&power_edge.from);
+        let (to, lhead) = writer.endpoint(&power_edge.to);
+
+        if ltail.is_some() && ltail == lhead {
+            continue;
+        }
+
+        let attributes: Vec<String> = [("ltail", ltail), ("lhead", lhead)]
+            .into_iter()
+            .filter_map(|(name, cluster)| cluster.map(|cluster| format!("{}={}", name, cluster)))
+            .collect();
+
+        if attributes.is_empty() {
+            writer.line(1, &format!("{} -> {};", from, to));
+        } else {
+            writer.line(
+                1,
+                &format!("{} -> {} [{}];", from, to, attributes.join(", ")),
+            );
+        }
+    }
+
+    for edge in power_edges
+        .iter()
+        .flat_map(|power_edge| power_edge.negative_edges.iter())
+    {
+        writer.line(
+            1,
+            &format!(
+                "{} -> {} [style=dashed, color=red];",
+                quote(&edge.from),
+                quote(&edge.to)
+            ),
+        );
+    }
+
+    writer.line(0, "}");
+    writer.output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_dot_digraph() {
+        let input = r#"
+            // An Airflow DAG
+            strict digraph "etl" {
+                rankdir = LR;
+                node [shape=box];
+                extract [label="Extract", owner=data];
+                extract -> transform -> load [weight=2];
+                subgraph cluster_reports {
+                    daily; weekly
+                }
+                load -> { daily weekly }
+                /* duplicate edges are ignored */
+                extract -> transform
+            }
+        "#;
+
+        let (nodes, edges) = read_dot(input).unwrap();
+
+        let ids: Vec<&str> = nodes.iter().map(|node| node.id.as_str()).collect();
+        assert_eq!(ids, vec!["extract", "transform", "load", "daily", "weekly"]);
+        assert_eq!(nodes[0].data, r#"{"label":"Extract","owner":"data"}"#);
+        assert_eq!(nodes[0].get_attributes().owner(), Some("data".to_string()));
+        assert_eq!(
+            edges,
+            vec![
+                Edge::new("extract", "transform"),
+                Edge::new("transform", "load"),
+                Edge::new("load", "daily"),
+                Edge::new("load", "weekly"),
+            ]
+        );
+    }
+
+    #[test]
+    fn read_dot_undirected_with_ports() {
+        let (nodes, edges) = read_dot("graph { a:n -- b:s:e; b--\"c d\" }").unwrap();
+
+        assert_eq!(nodes.len(), 3);
+        assert_eq!(edges, vec![Edge::new("a", "b"), Edge::new("b", "c d")]);
+    }
+
+    #[test]
+    fn read_dot_errors() {
+        assert!(matches!(
+            read_dot("digraph {\n a -> \n}"),
+            Err(PowerGraphError::Syntax { line: Some(3), .. })
+        ));
+        assert!(matches!(
+            read_dot("digraph { a -> b"),
+            Err(PowerGraphError::Syntax { .. })
+        ));
+        assert!(read_dot("tree { a }").is_err());
+    }
+
+    #[test]
+    fn write_dot_nested_clusters() {
+        let powergraph = crate::formats::tests::nested_powergraph();
+
+        let expected = r#"digraph powergraph {
+  compound=true;
+  node [shape=box];
+  edge [dir=none];
+  subgraph cluster_0 {
+    tooltip="a-b-c";
+    label="";
+    style=bold;
+    subgraph cluster_1 {
+      tooltip="a-b";
+      label="";
+      "a";
+      "b";
+    }
+    "c";
+  }
+  subgraph cluster_2 {
+    tooltip="d-e";
+    label="";
+    "d";
+    "e";
+  }
+  "say \"hi\"";
+  "a" -> "d" [ltail=cluster_1, lhead=cluster_2];
+  "c" -> "say \"hi\"";
+}
+"#;
+        assert_eq!(write_dot(&powergraph), expected);
+
+        let (nodes, edges) = read_dot(expected).unwrap();
+        assert_eq!(nodes.len(), 6);
+        assert_eq!(edges.len(), 2);
+    }
+
+    #[test]
+    fn write_dot_negative_edges() {
+        let mut powergraph = crate::formats::tests::nested_powergraph();
+        powergraph.power_edges[1].negative_edges = vec![crate::Edge::new("b", "e")];
+
+        let output = write_dot(&powergraph);
+
+        assert!(output.ends_with("  \"b\" -> \"e\" [style=dashed, color=red];\n}\n"));
+    }
+
+    #[test]
+    fn write_dot_directed_arrows() {
+        let mut powergraph = crate::formats::tests::nested_powergraph();
+        assert!(write_dot(&powergraph).contains("edge [dir=none];"));
+
+        powergraph.directed = true;
+        assert!(!write_dot(&powergraph).contains("dir=none"));
+    }
+}
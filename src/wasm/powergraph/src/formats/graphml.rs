@@ -0,0 +1,444 @@
+//! GraphML. When reading, nested graphs are flattened, and `<data>` values are
+//! carried into `Node.data` as a JSON object keyed by each `<key>`'s
+//! `attr.name`. When writing, power nodes become nodes holding nested graphs.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+
+use roxmltree::{Document, Node as XmlNode};
+use serde_json::{Map, Value};
+
+use super::{
+    check_edges, dedup_edges, dedup_nodes, node_attributes, syntax_error, unique_power_edges,
+    Nesting,
+};
+use crate::{error::PowerGraphError, Edge, Node, NodeAttributes, PowerGraph};
+
+const FORMAT: &str = "GraphML";
+
+fn attribute<'a>(element: &XmlNode<'a, '_>, name: &str) -> Result<&'a str, PowerGraphError> {
+    element.attribute(name).ok_or_else(|| {
+        let line = element.document().text_pos_at(element.range().start).row;
+        syntax_error(
+            FORMAT,
+            Some(line as usize),
+            format!(
+                "<{}> is missing the `{}` attribute",
+                element.tag_name().name(),
+                name
+            ),
+        )
+    })
+}
+
+/// Read a GraphML document.
+pub fn read_graphml(input: &str) -> Result<(Vec<Node>, Vec<Edge>), PowerGraphError> {
+    let document = Document::parse(input)
+        .map_err(|error| syntax_error(FORMAT, Some(error.pos().row as usize), error.to_string()))?;
+
+    let root = document.root_element();
+    if root.tag_name().name() != "graphml" {
+        return Err(syntax_error(
+            FORMAT,
+            None,
+            "the root element is not <graphml>".to_string(),
+        ));
+    }
+
+    let elements = |name: &'static str| {
+        root.descendants()
+            .filter(move |element| element.is_element() && element.tag_name().name() == name)
+    };
+
+    let mut key_names: HashMap<&str, &str> = HashMap::new();
+    for key in elements("key") {
+        let id = attribute(&key, "id")?;
+        key_names.insert(id, key.attribute("attr.name").unwrap_or(id));
+    }
+
+    let nodes = elements("node")
+        .map(|element| {
+            let id = attribute(&element, "id")?;
+            let mut data: BTreeMap<&str, String> = BTreeMap::new();
+
+            for child in element.children() {
+                if child.is_element() && child.tag_name().name() == "data" {
+                    let key = attribute(&child, "key")?;
+                    let name = key_names.get(key).copied().unwrap_or(key);
+                    data.insert(name, child.text().unwrap_or_default().to_string());
+                }
+            }
+
+            Ok(Node::new(id.to_string(), serde_json::to_string(&data)?)
+                .with_attributes(NodeAttributes::from_pairs(&data)))
+        })
+        .collect::<Result<Vec<Node>, PowerGraphError>>()?;
+
+    let edges = elements("edge")
+        .map(|element| {
+            Ok(Edge::new(
+                attribute(&element, "source")?,
+                attribute(&element, "target")?,
+            ))
+        })
+        .collect::<Result<Vec<Edge>, PowerGraphError>>()?;
+
+    check_edges(&nodes, &edges)?;
+
+    Ok((dedup_nodes(nodes), dedup_edges(edges)))
+}
+
+/// Escape text for use in XML content and attribute values.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// The GraphML `attr.type` for a JSON value, if it is a scalar.
+fn attribute_type(value: &Value) -> &'static str {
+    match value {
+        Value::Bool(_) => "boolean",
+        Value::Number(number) if number.is_i64() || number.is_u64() => "long",
+        Value::Number(_) => "double",
+        _ => "string",
+    }
+}
+
+/// The text of a `<data>` element for a JSON value. Arrays and objects are
+/// written as JSON.
+fn attribute_text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        value => value.to_string(),
+    }
+}
+
+struct GraphMLWriter<'a> {
+    powergraph: &'a PowerGraph,
+    nesting: Nesting<'a>,
+    attributes: Vec<Map<String, Value>>,
+    /// The `<key>` id for each node attribute name.
+    keys: HashMap<String, String>,
+    output: String,
+}
+
+impl GraphMLWriter<'_> {
+    fn line(&mut self, depth: usize, line: &str) {
+        let _ = writeln!(self.output, "{}{}", "  ".repeat(depth), line);
+    }
+
+    fn graph(&mut self, group: Option<usize>, depth: usize) {
+        for child in 0..self.nesting.groups.len() {
+            if self.nesting.group_parents[child] == group {
+                let id = Nesting::group_id(child);
+                self.line(depth, &format!("<node id=\"{}\">", id));
+                self.line(
+                    depth + 1,
+                    &format!(
+                        "<data key=\"power_node\">{}</data>",
+                        escape(&self.nesting.groups[child].id)
+                    ),
+                );
+                self.line(
+                    depth + 1,
+                    &format!("<graph id=\"{}:\" edgedefault=\"directed\">", id),
+                );
+                self.graph(Some(child), depth + 2);
+                self.line(depth + 1, "</graph>");
+                self.line(depth, "</node>");
+            }
+        }
+
+        for index in 0..self.powergraph.nodes.len() {
+            let node = &self.powergraph.nodes[index];
+            if self.nesting.node_parents.get(node.id.as_str()).copied() != group {
+                continue;
+            }
+
+            let data: Vec<String> = self.attributes[index]
+                .iter()
+                .filter(|(_, value)| !value.is_null())
+                .map(|(name, value)| {
+                    format!(
+                        "<data key=\"{}\">{}</data>",
+                        self.keys[name],
+                        escape(&attribute_text(value))
+                    )
+                })
+                .collect();
+
+            let id = escape(&node.id);
+            if data.is_empty() {
+                self.line(depth, &format!("<node id=\"{}\"/>", id));
+            } else {
+                self.line(depth, &format!("<node id=\"{}\">", id));
+                for data in data {
+                    self.line(depth + 1, &data);
+                }
+                self.line(depth, "</node>");
+            }
+        }
+    }
+}
+
+/// Write a decomposed power graph as GraphML. Each power node with more than
+/// one member is a node holding a nested graph, inside the smallest power node
+/// containing it. Node data is written as `<data>` elements, and every edge
+/// has a `kind` of `power`, `residual` for plain edges left over after
+/// decomposition, or `negative` for edges that a power edge from a lossy
+/// decomposition implies but that are not in the graph.
+pub fn write_graphml(powergraph: &PowerGraph) -> String {
+    let attributes: Vec<Map<String, Value>> =
+        powergraph.nodes.iter().map(node_attributes).collect();
+
+    // Use the narrowest type that fits every value of each attribute.
+    let mut types: BTreeMap<String, &str> = BTreeMap::new();
+    for (name, value) in attributes.iter().flatten() {
+        if value.is_null() {
+            continue;
+        }
+        let value_type = attribute_type(value);
+        types
+            .entry(name.clone())
+            .and_modify(|current| {
+                *current = match (*current, value_type) {
+                    (a, b) if a == b => a,
+                    ("long", "double") | ("double", "long") => "double",
+                    _ => "string",
+                }
+            })
+            .or_insert(value_type);
+    }
+
+    let mut writer = GraphMLWriter {
+        powergraph,
+        nesting: Nesting::new(powergraph),
+        keys: types
+            .keys()
+            .enumerate()
+            .map(|(index, name)| (name.clone(), format!("d{}", index)))
+            .collect(),
+        attributes,
+        output: String::new(),
+    };
+
+    writer.line(0, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>");
+    writer.line(
+        0,
+        "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">",
+    );
+    writer.line(
+        1,
+        "<key id=\"power_node\" for=\"node\" attr.name=\"power_node\" attr.type=\"string\"/>",
+    );
+    writer.line(
+        1,
+        "<key id=\"kind\" for=\"edge\" attr.name=\"kind\" attr.type=\"string\"/>",
+    );
+    for (index, (name, value_type)) in types.iter().enumerate() {
+        writer.line(
+            1,
+            &format!(
+                "<key id=\"d{}\" for=\"node\" attr.name=\"{}\" attr.type=\"{}\"/>",
+                index,
+                escape(name),
+                value_type
+            ),
+        );
+    }
+
+    writer.line(1, "<graph id=\"powergraph\" edgedefault=\"directed\">");
+    writer.graph(None, 2);
+
+    let power_edges = unique_power_edges(powergraph);
+    let edges = power_edges
+        .iter()
+        .map(|power_edge| {
+            let kind = if power_edge.residual {
+                "residual"
+            } else {
+                "power"
+            };
+            (
+                writer.nesting.endpoint_id(&power_edge.from),
+                writer.nesting.endpoint_id(&power_edge.to),
+                kind,
+            )
+        })
+        .chain(
+            power_edges
+                .iter()
+                .flat_map(|power_edge| power_edge.negative_edges.iter())
+                .map(|edge| (edge.from.clone(), edge.to.clone(), "negative")),
+        )
+        .collect::<Vec<(String, String, &str)>>();
+
+    for (source, target, kind) in edges {
+        writer.line(
+            2,
+            &format!(
+                "<edge source=\"{}\" target=\"{}\"><data key=\"kind\">{}</data></edge>",
+                escape(&source),
+                escape(&target),
+                kind
+            ),
+        );
+    }
+
+    writer.line(1, "</graph>");
+    writer.line(0, "</graphml>");
+    writer.output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GRAPHML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+        <graphml xmlns="http://graphml.graphdrawing.org/xmlns">
+          <key id="d0" for="node" attr.name="team" attr.type="string"/>
+          <graph id="services" edgedefault="directed">
+            <node id="api"><data key="d0">platform</data></node>
+            <node id="billing">
+              <graph id="billing:inner" edgedefault="directed">
+                <node id="invoices"/>
+              </graph>
+            </node>
+            <edge source="api" target="billing"/>
+            <edge id="e1" source="billing" target="invoices"/>
+          </graph>
+        </graphml>"#;
+
+    #[test]
+    fn read_graphml_graph() {
+        let (nodes, edges) = read_graphml(GRAPHML).unwrap();
+
+        let ids: Vec<&str> = nodes.iter().map(|node| node.id.as_str()).collect();
+        assert_eq!(ids, vec!["api", "billing", "invoices"]);
+        assert_eq!(nodes[0].data, r#"{"team":"platform"}"#);
+        assert_eq!(
+            nodes[0].get_attributes().get("team"),
+            Some("platform".to_string())
+        );
+        assert_eq!(
+            edges,
+            vec![
+                Edge::new("api", "billing"),
+                Edge::new("billing", "invoices")
+            ]
+        );
+    }
+
+    #[test]
+    fn read_graphml_repeated_nodes() {
+        let input = r#"<graphml>
+              <graph edgedefault="directed">
+                <node id="a"><data key="team">platform</data></node>
+                <node id="b"/>
+                <node id="a"/>
+                <edge source="a" target="b"/>
+              </graph>
+            </graphml>"#;
+
+        let (nodes, _) = read_graphml(input).unwrap();
+
+        let ids: Vec<&str> = nodes.iter().map(|node| node.id.as_str()).collect();
+        assert_eq!(ids, vec!["a", "b"]);
+        assert_eq!(nodes[0].data, r#"{"team":"platform"}"#);
+    }
+
+    #[test]
+    fn read_graphml_errors() {
+        let unknown = GRAPHML.replace(r#"target="invoices""#, r#"target="ledger""#);
+        assert!(matches!(
+            read_graphml(&unknown),
+            Err(PowerGraphError::UnknownNode { id, .. }) if id == "ledger"
+        ));
+
+        let missing = GRAPHML.replace(r#"<node id="invoices"/>"#, "<node/>");
+        assert!(matches!(
+            read_graphml(&missing),
+            Err(PowerGraphError::Syntax { line: Some(8), .. })
+        ));
+
+        assert!(matches!(
+            read_graphml("<graphml><graph></graphml>"),
+            Err(PowerGraphError::Syntax { line: Some(1), .. })
+        ));
+    }
+
+    #[test]
+    fn write_graphml_nested_graphs() {
+        let powergraph = crate::formats::tests::nested_powergraph();
+
+        let output = write_graphml(&powergraph);
+
+        assert!(output.contains(r#"<key id="d1" for="node" attr.name="rows" attr.type="long"/>"#));
+        assert!(output.contains(r#"<data key="d2">[&quot;x&quot;]</data>"#));
+
+        let document = Document::parse(&output).unwrap();
+        let element = |id: &str| {
+            document
+                .descendants()
+                .find(|element| element.attribute("id") == Some(id))
+                .unwrap()
+        };
+        let parent_node = |id: &str| {
+            element(id)
+                .ancestors()
+                .skip(1)
+                .find(|ancestor| ancestor.has_tag_name("node"))
+                .and_then(|ancestor| ancestor.attribute("id"))
+        };
+        assert_eq!(parent_node("a"), Some("power_node_1"));
+        assert_eq!(parent_node("power_node_1"), Some("power_node_0"));
+        assert_eq!(parent_node("c"), Some("power_node_0"));
+        assert_eq!(parent_node("say \"hi\""), None);
+
+        let kinds: Vec<(&str, &str, &str)> = document
+            .descendants()
+            .filter(|element| element.has_tag_name("edge"))
+            .map(|edge| {
+                (
+                    edge.attribute("source").unwrap(),
+                    edge.attribute("target").unwrap(),
+                    edge.first_element_child().unwrap().text().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ("power_node_0", "power_node_0", "power"),
+                ("power_node_1", "power_node_2", "power"),
+                ("c", "say \"hi\"", "residual"),
+            ]
+        );
+
+        let (nodes, edges) = read_graphml(&output).unwrap();
+        assert_eq!(nodes.len(), 9);
+        assert_eq!(edges.len(), 3);
+    }
+
+    #[test]
+    fn write_graphml_negative_edges() {
+        let mut powergraph = crate::formats::tests::nested_powergraph();
+        powergraph.power_edges[1].negative_edges = vec![Edge::new("b", "e")];
+
+        let output = write_graphml(&powergraph);
+
+        assert!(output
+            .contains(r#"<edge source="b" target="e"><data key="kind">negative</data></edge>"#));
+        assert_eq!(read_graphml(&output).unwrap().1.len(), 4);
+    }
+}
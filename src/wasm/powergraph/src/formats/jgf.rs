@@ -0,0 +1,156 @@
+//! JSON Graph Format, both v1 (`nodes` as an array) and v2 (`nodes` keyed by
+//! id). Documents with multiple `graphs` are merged into a single graph, with
+//! a node in several graphs taken from the first.
+
+use serde_json::{Map, Value};
+
+use super::{check_edges, dedup_edges, dedup_nodes, json_attributes, syntax_error};
+use crate::{error::PowerGraphError, Edge, Node};
+
+const FORMAT: &str = "JSON Graph";
+
+fn invalid(message: &str) -> PowerGraphError {
+    syntax_error(FORMAT, None, message.to_string())
+}
+
+fn read_nodes(graph: &Map<String, Value>) -> Result<Vec<Node>, PowerGraphError> {
+    match graph.get("nodes") {
+        None => Ok(vec![]),
+        Some(Value::Object(nodes)) => nodes
+            .iter()
+            .map(|(id, node)| {
+                let attributes = node.as_object().map(json_attributes).unwrap_or_default();
+                Ok(Node::new(id.clone(), serde_json::to_string(node)?).with_attributes(attributes))
+            })
+            .collect(),
+        Some(Value::Array(nodes)) => nodes
+            .iter()
+            .map(|node| {
+                let mut node = node
+                    .as_object()
+                    .cloned()
+                    .ok_or_else(|| invalid("nodes must be objects"))?;
+                let id = match node.remove("id") {
+                    Some(Value::String(id)) => id,
+                    Some(Value::Number(id)) => id.to_string(),
+                    _ => return Err(invalid("every node must have an `id`")),
+                };
+                Ok(Node::new(id, serde_json::to_string(&node)?)
+                    .with_attributes(json_attributes(&node)))
+            })
+            .collect(),
+        Some(_) => Err(invalid("`nodes` must be an object or an array")),
+    }
+}
+
+fn read_edges(graph: &Map<String, Value>) -> Result<Vec<Edge>, PowerGraphError> {
+    let edges = match graph.get("edges") {
+        None => return Ok(vec![]),
+        Some(Value::Array(edges)) => edges,
+        Some(_) => return Err(invalid("`edges` must be an array")),
+    };
+
+    let endpoint = |edge: &Value, name: &str| match edge.get(name) {
+        Some(Value::String(id)) => Ok(id.clone()),
+        Some(Value::Number(id)) => Ok(id.to_string()),
+        _ => Err(invalid(&format!("every edge must have a `{}`", name))),
+    };
+
+    edges
+        .iter()
+        .map(|edge| {
+            Ok(Edge::new(
+                &endpoint(edge, "source")?,
+                &endpoint(edge, "target")?,
+            ))
+        })
+        .collect()
+}
+
+/// Read a JSON Graph Format document.
+pub fn read_jgf(input: &str) -> Result<(Vec<Node>, Vec<Edge>), PowerGraphError> {
+    let document: Value = serde_json::from_str(input)?;
+
+    let graphs: Vec<&Map<String, Value>> = match (document.get("graph"), document.get("graphs")) {
+        (Some(Value::Object(graph)), _) => vec![graph],
+        (None, Some(Value::Array(graphs))) => graphs
+            .iter()
+            .map(|graph| {
+                graph
+                    .as_object()
+                    .ok_or_else(|| invalid("graphs must be objects"))
+            })
+            .collect::<Result<Vec<&Map<String, Value>>, PowerGraphError>>()?,
+        _ => return Err(invalid("expected a top-level `graph` or `graphs`")),
+    };
+
+    let mut nodes = Vec::new();
+    let mut edges = Vec::new();
+    for graph in graphs {
+        nodes.extend(read_nodes(graph)?);
+        edges.extend(read_edges(graph)?);
+    }
+
+    check_edges(&nodes, &edges)?;
+
+    Ok((dedup_nodes(nodes), dedup_edges(edges)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_jgf_v2() {
+        let input = r#"{
+            "graph": {
+                "directed": true,
+                "nodes": {
+                    "extract": {"label": "Extract"},
+                    "load": {"metadata": {"owner": "data"}}
+                },
+                "edges": [{"source": "extract", "target": "load"}]
+            }
+        }"#;
+
+        let (nodes, edges) = read_jgf(input).unwrap();
+
+        assert_eq!(nodes.len(), 2);
+        let extract = nodes.iter().find(|node| node.id == "extract").unwrap();
+        assert_eq!(extract.data, r#"{"label":"Extract"}"#);
+        let load = nodes.iter().find(|node| node.id == "load").unwrap();
+        assert_eq!(load.get_attributes().owner(), Some("data".to_string()));
+        assert_eq!(edges, vec![Edge::new("extract", "load")]);
+    }
+
+    #[test]
+    fn read_jgf_v1_multiple_graphs() {
+        let input = r#"{
+            "graphs": [
+                {"nodes": [{"id": "a"}, {"id": 1, "label": "one"}], "edges": [{"source": "a", "target": 1}]},
+                {"nodes": [{"id": "b"}, {"id": "a", "label": "again"}], "edges": [{"source": "b", "target": "a"}]}
+            ]
+        }"#;
+
+        let (nodes, edges) = read_jgf(input).unwrap();
+
+        let ids: Vec<&str> = nodes.iter().map(|node| node.id.as_str()).collect();
+        assert_eq!(ids, vec!["a", "1", "b"]);
+        assert_eq!(nodes[0].data, "{}");
+        assert_eq!(nodes[1].data, r#"{"label":"one"}"#);
+        assert_eq!(edges, vec![Edge::new("a", "1"), Edge::new("b", "a")]);
+    }
+
+    #[test]
+    fn read_jgf_errors() {
+        assert!(matches!(
+            read_jgf(r#"{"graph": {"nodes": [], "edges": [{"source": "a", "target": "b"}]}}"#),
+            Err(PowerGraphError::UnknownNode { .. })
+        ));
+        assert!(matches!(
+            read_jgf(r#"{"nodes": []}"#),
+            Err(PowerGraphError::Syntax { .. })
+        ));
+        assert!(matches!(read_jgf("{"), Err(PowerGraphError::Parse(_))));
+    }
+}
@@ -0,0 +1,282 @@
+//! Readers for graph formats other than dbt manifests, and writers for
+//! decomposed power graphs. Each reader produces the `Vec<Node>` and
+//! `Vec<Edge>` accepted by `PowerGraph::new`.
+
+pub mod csv;
+pub mod cytoscape;
+pub mod dot;
+pub mod graphml;
+pub mod jgf;
+
+use std::collections::{HashMap, HashSet};
+
+use itertools::Itertools;
+use serde_json::{Map, Value};
+
+use crate::{error::PowerGraphError, Edge, Node, NodeAttributes, PowerEdge, PowerGraph, PowerNode};
+
+fn syntax_error(format: &'static str, line: Option<usize>, message: String) -> PowerGraphError {
+    PowerGraphError::Syntax {
+        format,
+        line,
+        message,
+    }
+}
+
+/// Add an empty node for every edge endpoint that is not already in `nodes`,
+/// for formats where edges implicitly declare their nodes.
+fn with_implicit_nodes(mut nodes: Vec<Node>, edges: &[Edge]) -> Vec<Node> {
+    let mut known: HashSet<String> = nodes.iter().map(|node| node.id.clone()).collect();
+
+    for id in edges.iter().flat_map(|edge| [&edge.from, &edge.to]) {
+        if known.insert(id.clone()) {
+            nodes.push(Node::new(id.clone(), "{}".to_string()));
+        }
+    }
+
+    nodes
+}
+
+/// Ensure that every edge references a declared node, for formats where nodes
+/// must be declared explicitly.
+fn check_edges(nodes: &[Node], edges: &[Edge]) -> Result<(), PowerGraphError> {
+    let known: HashSet<&str> = nodes.iter().map(|node| node.id.as_str()).collect();
+
+    for edge in edges {
+        for id in [&edge.from, &edge.to] {
+            if !known.contains(id.as_str()) {
+                return Err(PowerGraphError::UnknownNode {
+                    id: id.clone(),
+                    referenced_by: edge.get_id(),
+                });
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn dedup_edges(edges: Vec<Edge>) -> Vec<Edge> {
+    edges.into_iter().unique().collect()
+}
+
+/// Keep the first declaration of each node, for formats where a node may be
+/// declared more than once.
+fn dedup_nodes(nodes: Vec<Node>) -> Vec<Node> {
+    nodes
+        .into_iter()
+        .unique_by(|node| node.id.clone())
+        .collect()
+}
+
+/// The attributes of a node: its data if that is a JSON object, and otherwise
+/// the data as a single `data` attribute.
+fn node_attributes(node: &Node) -> Map<String, Value> {
+    match serde_json::from_str(&node.data) {
+        Ok(Value::Object(attributes)) => attributes,
+        _ => Map::from_iter([("data".to_string(), Value::String(node.data.clone()))]),
+    }
+}
+
+/// Typed attributes from the scalar fields of a JSON node object, and of its
+/// `metadata` object if it has one. Lists of strings are kept for `tags` and
+/// `fqn`.
+fn json_attributes(object: &Map<String, Value>) -> NodeAttributes {
+    let metadata = match object.get("metadata") {
+        Some(Value::Object(metadata)) => Some(metadata),
+        _ => None,
+    };
+
+    let pairs = object
+        .iter()
+        .chain(metadata.into_iter().flatten())
+        .filter_map(|(key, value)| {
+            let value = match value {
+                Value::String(value) => value.clone(),
+                Value::Number(value) => value.to_string(),
+                Value::Bool(value) => value.to_string(),
+                Value::Array(values) if key == "tags" || key == "fqn" => {
+                    let separator = if key == "tags" { "," } else { "." };
+                    values.iter().filter_map(Value::as_str).join(separator)
+                }
+                _ => return None,
+            };
+            Some((key, value))
+        });
+
+    NodeAttributes::from_pairs(pairs)
+}
+
+/// Power nodes arranged as nested groups, for formats that draw a power node
+/// as a container around its members. Power nodes with a single member are
+/// left out so that singletons are drawn as plain nodes.
+struct Nesting<'a> {
+    /// Power nodes with more than one member, largest first.
+    groups: Vec<&'a PowerNode>,
+    /// The smallest group strictly containing each group, by index into `groups`.
+    group_parents: Vec<Option<usize>>,
+    /// The smallest group containing each node, by index into `groups`.
+    node_parents: HashMap<&'a str, usize>,
+    /// The index into `groups` of every group, by power node id.
+    group_index: HashMap<&'a str, usize>,
+}
+
+impl<'a> Nesting<'a> {
+    fn new(powergraph: &'a PowerGraph) -> Nesting<'a> {
+        let mut groups: Vec<&PowerNode> = powergraph
+            .power_nodes
+            .iter()
+            .filter(|power_node| power_node.cluster.items.len() > 1)
+            .unique_by(|power_node| &power_node.id)
+            .collect();
+        groups.sort_by(|a, b| {
+            b.cluster
+                .items
+                .len()
+                .cmp(&a.cluster.items.len())
+                .then_with(|| a.id.cmp(&b.id))
+        });
+
+        // Groups are ordered largest first, so the last containing group seen
+        // is the smallest one.
+        let group_parents = groups
+            .iter()
+            .enumerate()
+            .map(|(index, group)| {
+                groups[..index].iter().rposition(|candidate| {
+                    group
+                        .cluster
+                        .items
+                        .is_proper_subset_of(&candidate.cluster.items)
+                })
+            })
+            .collect();
+
+        let mut node_parents = HashMap::new();
+        for (index, group) in groups.iter().enumerate() {
+            for item in group.named.items.iter() {
+                node_parents.insert(item.as_str(), index);
+            }
+        }
+
+        let group_index = groups
+            .iter()
+            .enumerate()
+            .map(|(index, group)| (group.id.as_str(), index))
+            .collect();
+
+        Nesting {
+            groups,
+            group_parents,
+            node_parents,
+            group_index,
+        }
+    }
+
+    /// The id of a group in formats where groups are nodes themselves.
+    fn group_id(group: usize) -> String {
+        format!("power_node_{}", group)
+    }
+
+    /// The id to use for a power edge endpoint in formats where groups are
+    /// nodes themselves.
+    fn endpoint_id(&self, id: &str) -> String {
+        match self.group_index.get(id) {
+            Some(&group) => Nesting::group_id(group),
+            None => id.to_string(),
+        }
+    }
+
+    /// The sorted members of a group.
+    fn members(&self, group: usize) -> Vec<&'a str> {
+        let mut members: Vec<&str> = self.groups[group]
+            .named
+            .items
+            .iter()
+            .map(|item| item.as_str())
+            .collect();
+        members.sort();
+        members
+    }
+}
+
+/// Power edges without duplicates, in decomposition order.
+fn unique_power_edges(powergraph: &PowerGraph) -> Vec<&PowerEdge> {
+    powergraph.power_edges.iter().unique().collect()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::{clusters::Cluster, node_set::NodeSet};
+
+    /// A power node of the given nodes of `powergraph`.
+    pub(crate) fn power_node(powergraph: &PowerGraph, items: &[&str]) -> PowerNode {
+        let cluster = Cluster::new(
+            powergraph.interner.set(items.iter().copied()),
+            NodeSet::new(),
+        );
+        PowerNode::new(cluster, &powergraph.interner)
+    }
+
+    pub(crate) fn power_edge(from: &str, to: &str, residual: bool) -> PowerEdge {
+        PowerEdge {
+            from: from.to_string(),
+            to: to.to_string(),
+            residual,
+            negative_edges: Vec::new(),
+        }
+    }
+
+    /// A decomposition with a clique `a-b-c` containing `a-b`, which points at
+    /// `d-e`, and a residual edge from `c` to a node that needs quoting.
+    pub(crate) fn nested_powergraph() -> PowerGraph {
+        let mut nodes: Vec<Node> = ["b", "c", "d", "e", "say \"hi\""]
+            .iter()
+            .map(|id| Node::new(id.to_string(), "{}".to_string()))
+            .collect();
+        nodes.insert(
+            0,
+            Node::new(
+                "a".to_string(),
+                r#"{"name":"a","tags":["x"],"rows":3}"#.to_string(),
+            ),
+        );
+        let mut powergraph = PowerGraph::new(nodes, vec![]);
+
+        powergraph.set_power_nodes(vec![
+            power_node(&powergraph, &["a", "b", "c"]),
+            power_node(&powergraph, &["a", "b"]),
+            power_node(&powergraph, &["d", "e"]),
+            power_node(&powergraph, &["a", "b"]),
+            power_node(&powergraph, &["c"]),
+        ]);
+        powergraph.power_edges = vec![
+            power_edge("a-b-c", "a-b-c", false),
+            power_edge("a-b", "d-e", false),
+            power_edge("a-b", "d-e", false),
+            power_edge("c", "say \"hi\"", true),
+        ];
+
+        powergraph
+    }
+
+    #[test]
+    fn nesting_groups() {
+        let powergraph = nested_powergraph();
+        let nesting = Nesting::new(&powergraph);
+
+        let ids: Vec<&str> = nesting
+            .groups
+            .iter()
+            .map(|group| group.id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["a-b-c", "a-b", "d-e"]);
+        assert_eq!(nesting.group_parents, vec![None, Some(0), None]);
+        assert_eq!(nesting.node_parents["a"], 1);
+        assert_eq!(nesting.node_parents["c"], 0);
+        assert!(!nesting.node_parents.contains_key("say \"hi\""));
+        assert_eq!(nesting.members(1), vec!["a", "b"]);
+        assert_eq!(unique_power_edges(&powergraph).len(), 3);
+    }
+}
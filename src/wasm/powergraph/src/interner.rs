@@ -0,0 +1,84 @@
+//! Dense integer indices for node ids, so that the decomposition works on
+//! small integers and bitsets rather than cloning and hashing strings.
+
+use crate::node_set::NodeSet;
+
+/// The index of an interned node id.
+pub type NodeIndex = u32;
+
+/// Maps node ids to dense indices and back. Ids are numbered in sorted order,
+/// so that ordering by index is the same as ordering by id, and anything
+/// ordered by index is ordered as it would be by id.
+#[derive(Debug, Clone, Default)]
+pub struct Interner {
+    names: Vec<String>,
+}
+
+impl Interner {
+    pub fn new<'a>(names: impl IntoIterator<Item = &'a str>) -> Interner {
+        let mut names: Vec<String> = names.into_iter().map(str::to_string).collect();
+        names.sort();
+        names.dedup();
+
+        Interner { names }
+    }
+
+    /// The index of `name`, if it was interned.
+    pub fn index(&self, name: &str) -> Option<NodeIndex> {
+        self.names
+            .binary_search_by(|interned| interned.as_str().cmp(name))
+            .ok()
+            .map(|index| index as NodeIndex)
+    }
+
+    pub fn name(&self, index: NodeIndex) -> &str {
+        &self.names[index as usize]
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    /// The set of the interned `names`, ignoring any that were not interned.
+    #[cfg(test)]
+    pub fn set<'a>(&self, names: impl IntoIterator<Item = &'a str>) -> NodeSet {
+        names
+            .into_iter()
+            .filter_map(|name| self.index(name))
+            .collect()
+    }
+
+    /// The names of the members of `set`, in sorted order.
+    pub fn names<'a>(&'a self, set: &'a NodeSet) -> impl Iterator<Item = &'a str> + 'a {
+        set.iter().map(|index| self.name(index))
+    }
+
+    /// The names of the members of `set` joined with "-", as used for the ids
+    /// of power nodes.
+    pub fn join(&self, set: &NodeSet) -> String {
+        self.names(set).collect::<Vec<&str>>().join("-")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interned_in_name_order() {
+        let interner = Interner::new(["model.b", "model.a", "seed.c", "model.a"]);
+
+        assert_eq!(interner.len(), 3);
+        assert_eq!(interner.index("model.a"), Some(0));
+        assert_eq!(interner.index("seed.c"), Some(2));
+        assert_eq!(interner.index("model.z"), None);
+        assert_eq!(interner.name(1), "model.b");
+
+        let set = interner.set(["seed.c", "model.a", "model.z"]);
+        assert_eq!(
+            interner.names(&set).collect::<Vec<&str>>(),
+            ["model.a", "seed.c"]
+        );
+        assert_eq!(interner.join(&set), "model.a-seed.c");
+    }
+}
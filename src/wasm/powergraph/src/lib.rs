@@ -1,30 +1,52 @@
+mod attributes;
 mod cluster_repository;
 mod clusters;
+mod components;
+mod decomposition;
+mod edge_coverage;
 mod edge_repository;
+mod error;
+pub mod formats;
+mod interner;
+pub mod manifest;
+mod node_set;
+mod options;
+mod parallel;
+pub mod progress;
+pub mod selector;
 mod sets;
+pub mod similarity;
 mod similarity_matrix;
+mod stats;
 mod unordered_tuple;
 mod utils;
+mod verify;
 
-use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap};
 use std::hash::Hash;
 use std::hash::Hasher;
 
-use std::process::exit;
-use std::time::Instant;
-
+pub use attributes::NodeAttributes;
 use cluster_repository::ClusterRepository;
-use clusters::Cluster;
+use clusters::{Cluster, NamedCluster};
+pub use decomposition::Decomposition;
+use decomposition::DecompositionState;
+use edge_coverage::EdgeCoverage;
 use edge_repository::EdgeRepository;
+pub use error::PowerGraphError;
+use interner::{Interner, NodeIndex};
 use itertools::Itertools;
+use node_set::NodeSet;
+pub use options::{CandidateScoring, DecompositionOptions};
+use progress::{JsProgress, NoProgress, Progress};
 use serde::Serialize;
-use sets::Set;
-use similarity_matrix::SimilarityMatrix;
+use similarity::Similarity;
+pub use stats::{DecompositionStats, PhaseTiming};
 use unordered_tuple::UnorderedTuple;
 use utils::set_panic_hook;
+pub use verify::{PowerEdgeOverlap, VerificationReport};
 use wasm_bindgen::prelude::*;
-
-use log::{info, trace};
+use web_sys::AbortSignal;
 
 #[wasm_bindgen(start)]
 fn start() {
@@ -52,12 +74,13 @@ macro_rules! console_log {
         let value  = format_args!($($t)*).to_string();
 
         #[cfg(target_arch = "wasm32")]
-        log(&value);
+        $crate::log(&value);
 
         #[cfg(not(target_arch = "wasm32"))]
-        info!("{}", &value);
+        ::log::info!("{}", &value);
     }
 }
+pub(crate) use console_log;
 
 macro_rules! console_debug {
     // Note that this is using the `log` function imported above during
@@ -67,9 +90,10 @@ macro_rules! console_debug {
         // #[cfg(target_arch = "wasm32")]
         // debug(&value);
         #[cfg(not(target_arch = "wasm32"))]
-        trace!("{}", &value);
+        ::log::trace!("{}", &value);
     }
 }
+pub(crate) use console_debug;
 
 #[wasm_bindgen]
 pub fn greet(value: &str) {
@@ -83,14 +107,73 @@ type NodeId = String;
 pub struct Node {
     id: NodeId,
     data: String,
+    #[serde(skip_serializing_if = "is_zero")]
+    test_count: usize,
+    /// The dbt project this node belongs to, when several projects are merged.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    project: Option<String>,
+    #[serde(skip_serializing_if = "NodeAttributes::is_empty")]
+    attributes: NodeAttributes,
 }
 
 #[wasm_bindgen]
 impl Node {
     #[wasm_bindgen]
     pub fn new(id: NodeId, data: String) -> Node {
-        Node { id, data }
+        Node {
+            id,
+            data,
+            test_count: 0,
+            project: None,
+            attributes: NodeAttributes::default(),
+        }
+    }
+
+    /// Set the number of data tests folded into this node.
+    #[wasm_bindgen]
+    pub fn with_test_count(mut self, test_count: usize) -> Node {
+        self.test_count = test_count;
+        self
+    }
+
+    /// Set the dbt project this node belongs to.
+    #[wasm_bindgen]
+    pub fn with_project(mut self, project: String) -> Node {
+        self.project = Some(project);
+        self
+    }
+
+    /// Set the typed attributes of this node.
+    #[wasm_bindgen]
+    pub fn with_attributes(mut self, attributes: NodeAttributes) -> Node {
+        self.attributes = attributes;
+        self
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn id(&self) -> String {
+        self.id.clone()
     }
+
+    #[wasm_bindgen(getter)]
+    pub fn data(&self) -> String {
+        self.data.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn attributes(&self) -> NodeAttributes {
+        self.attributes.clone()
+    }
+}
+
+impl Node {
+    pub fn get_attributes(&self) -> &NodeAttributes {
+        &self.attributes
+    }
+}
+
+fn is_zero(value: &usize) -> bool {
+    *value == 0
 }
 
 #[wasm_bindgen]
@@ -122,6 +205,18 @@ impl Eq for Edge {
     // }
 }
 
+impl PartialOrd for Edge {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Edge {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (&self.from, &self.to).cmp(&(&other.from, &other.to))
+    }
+}
+
 #[wasm_bindgen]
 impl Edge {
     #[wasm_bindgen]
@@ -151,14 +246,111 @@ type PowerNodeId = String;
 #[derive(Debug, Clone, Serialize)]
 pub struct PowerNode {
     id: PowerNodeId,
+    /// The cluster with the ids of its members, as it is serialized.
+    #[serde(rename = "cluster")]
+    named: NamedCluster,
+    #[serde(skip)]
     cluster: Cluster,
+    /// The total number of data tests on the nodes within this power node.
+    #[serde(skip_serializing_if = "is_zero")]
+    test_count: usize,
+    /// The sorted, distinct projects of the nodes within this power node.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    projects: Vec<String>,
+    /// The attributes shared by every node within this power node.
+    #[serde(skip_serializing_if = "NodeAttributes::is_empty")]
+    attributes: NodeAttributes,
+    /// The smallest power node strictly containing this one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    parent: Option<PowerNodeId>,
+    /// The sorted ids of the power nodes whose parent is this one.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    children: Vec<PowerNodeId>,
+    /// The number of power nodes containing this one.
+    depth: usize,
+}
+
+impl PowerNode {
+    fn new(cluster: Cluster, interner: &Interner) -> PowerNode {
+        let named = cluster.named(interner);
+        PowerNode {
+            id: named.id.clone(),
+            named,
+            cluster,
+            test_count: 0,
+            projects: Vec::new(),
+            attributes: NodeAttributes::default(),
+            parent: None,
+            children: Vec::new(),
+            depth: 0,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl PowerNode {
+    #[wasm_bindgen(getter)]
+    pub fn id(&self) -> String {
+        self.id.clone()
+    }
+
+    /// The ids of the nodes within this power node.
+    #[wasm_bindgen(getter)]
+    pub fn items(&self) -> Vec<String> {
+        self.named.items.to_vec()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn test_count(&self) -> usize {
+        self.test_count
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn projects(&self) -> Vec<String> {
+        self.projects.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn attributes(&self) -> NodeAttributes {
+        self.attributes.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn parent(&self) -> Option<String> {
+        self.parent.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn children(&self) -> Vec<String> {
+        self.children.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
 }
 
+/// An edge between every node of one power node and every node of another.
+/// In a directed decomposition every node in `from` has an edge to every node
+/// in `to`; otherwise the edges may point either way.
 #[wasm_bindgen]
-#[derive(Debug, PartialEq, Clone, Serialize)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize)]
 pub struct PowerEdge {
     from: PowerNodeId,
     to: PowerNodeId,
+    /// Whether this is a plain edge added by the final coverage pass of
+    /// `decompose`, rather than an edge found between power nodes.
+    #[serde(skip_serializing_if = "is_false")]
+    residual: bool,
+    /// Pairs of nodes in `from` and `to` without an edge between them, which
+    /// the power edge does not imply. Only lossy decompositions have these.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    negative_edges: Vec<Edge>,
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
 }
 
 #[derive(Debug, Clone)]
@@ -189,27 +381,50 @@ impl PartialOrd for PowerEdgeCandidate {
     }
 }
 
+/// Candidates are ordered by size. Candidates of the same size are ordered by
+/// their clusters, so that the first in cluster order is the greatest and is
+/// processed first, whatever order the candidates were found in.
 impl Ord for PowerEdgeCandidate {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        ((self.size * 10000.0) as u32).cmp(&((other.size * 10000.0) as u32))
+        self.size
+            .total_cmp(&other.size)
+            .then_with(|| other.from.cmp(&self.from))
+            .then_with(|| other.to.cmp(&self.to))
     }
 }
 
 #[derive(Debug)]
+#[allow(clippy::enum_variant_names)]
 enum PowerEdgeCandidateProcessorOutput {
     NewPowerEdgeCandidate(PowerEdgeCandidate),
-    NewPowerNode(PowerNode),
+    NewPowerNode(Box<PowerNode>),
     NewPowerEdge(PowerEdge),
 }
 
 #[wasm_bindgen]
-#[derive(Serialize)]
+#[derive(Clone, Serialize)]
 pub struct PowerGraph {
     nodes: Vec<Node>,
     edges: EdgeRepository,
     power_nodes: Vec<PowerNode>,
     power_edges: Vec<PowerEdge>,
-    clusters: Vec<Cluster>,
+    clusters: Vec<NamedCluster>,
+    /// Edges derived from relationship tests. These are carried through to the
+    /// output, but do not participate in the decomposition.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    test_edges: Vec<Edge>,
+    /// Whether the decomposition respected the direction of edges.
+    #[serde(skip_serializing_if = "is_false")]
+    directed: bool,
+    /// The time taken by each phase of the last decomposition.
+    #[serde(skip)]
+    timings: Vec<PhaseTiming>,
+    /// The indices of the ids of the nodes and of the ends of edges.
+    #[serde(skip)]
+    interner: Interner,
+    /// The position in `power_nodes` of the first power node with each id.
+    #[serde(skip)]
+    power_node_positions: HashMap<PowerNodeId, usize>,
 }
 
 #[wasm_bindgen]
@@ -218,89 +433,165 @@ impl PowerGraph {
     pub fn new(nodes: Vec<Node>, edges: Vec<Edge>) -> PowerGraph {
         // console::log_1(&"Hello using web-sys".into());
 
-        let edge_repository = EdgeRepository::from_edge_list(edges);
+        let interner = Interner::new(
+            nodes.iter().map(|node| node.id.as_str()).chain(
+                edges
+                    .iter()
+                    .flat_map(|edge| [edge.from.as_str(), edge.to.as_str()]),
+            ),
+        );
+        let edge_repository = EdgeRepository::from_edge_list(edges, &interner);
 
         PowerGraph {
             nodes,
             edges: edge_repository,
             power_edges: Vec::new(),
             power_nodes: Vec::new(),
+            power_node_positions: HashMap::new(),
             clusters: Vec::new(),
+            test_edges: Vec::new(),
+            directed: false,
+            timings: Vec::new(),
+            interner,
         }
     }
 
+    #[wasm_bindgen]
+    pub fn set_test_edges(&mut self, test_edges: Vec<Edge>) {
+        self.test_edges = test_edges;
+    }
+
     #[wasm_bindgen]
     pub fn to_object(&self) -> JsValue {
         serde_wasm_bindgen::to_value(self).unwrap()
     }
 
-    // Given a from index and to index, return the edge if it exists in the graph.
-    fn get_edge(&self, from: &NodeId, to: &NodeId) -> Option<Edge> {
-        self.edges.get_edge(from, to)
+    /// Render the decomposition as a Graphviz DOT digraph.
+    #[wasm_bindgen]
+    pub fn to_dot(&self) -> String {
+        formats::dot::write_dot(self)
+    }
+
+    /// Render the decomposition as GraphML with power nodes as nested graphs.
+    #[wasm_bindgen]
+    pub fn to_graphml(&self) -> String {
+        formats::graphml::write_graphml(self)
+    }
+
+    /// Render the decomposition as Cytoscape.js elements with power nodes as
+    /// compound nodes.
+    #[wasm_bindgen]
+    pub fn to_cytoscape(&self) -> JsValue {
+        formats::cytoscape::write_cytoscape(self)
+            .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+            .unwrap()
+    }
+
+    #[wasm_bindgen]
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    #[wasm_bindgen]
+    pub fn edge_count(&self) -> usize {
+        self.edges.len()
+    }
+
+    #[wasm_bindgen]
+    pub fn power_node_count(&self) -> usize {
+        self.power_nodes.len()
+    }
+
+    #[wasm_bindgen]
+    pub fn power_edge_count(&self) -> usize {
+        self.power_edges.len()
     }
 
     fn find_power_node(&self, search_id: &str) -> Option<&PowerNode> {
-        self.power_nodes.iter().find(|pn| pn.id == search_id)
+        self.power_node_positions
+            .get(search_id)
+            .map(|&position| &self.power_nodes[position])
+    }
+
+    /// Add a power node, finding it by id unless a power node with the same id
+    /// was added before.
+    fn add_power_node(&mut self, power_node: PowerNode) {
+        self.power_node_positions
+            .entry(power_node.id.clone())
+            .or_insert(self.power_nodes.len());
+        self.power_nodes.push(power_node);
+    }
+
+    /// Replace the power nodes, such as once duplicates are removed.
+    fn set_power_nodes(&mut self, power_nodes: Vec<PowerNode>) {
+        self.power_nodes.clear();
+        self.power_node_positions.clear();
+        for power_node in power_nodes {
+            self.add_power_node(power_node);
+        }
+    }
+
+    /// The cluster in `cluster_repository` with the items of the power node
+    /// with the given id.
+    fn power_edge_cluster<'a>(
+        &self,
+        id: &str,
+        cluster_repository: &'a ClusterRepository,
+    ) -> Option<&'a Cluster> {
+        self.find_power_node(id)
+            .and_then(|power_node| cluster_repository.get(&power_node.cluster.items))
+    }
+
+    /// The edges joining the nodes of a power edge, by node index.
+    fn power_edge_subgraph(&self, power_edge: &PowerEdge) -> Vec<(NodeIndex, NodeIndex)> {
+        let source_power_node = self.find_power_node(&power_edge.from).unwrap();
+        let target_power_node = self.find_power_node(&power_edge.to).unwrap();
+
+        self.subgraph(
+            &source_power_node
+                .cluster
+                .items
+                .union(&target_power_node.cluster.items),
+        )
     }
 
     fn expand_power_edge(&self, power_edge: &PowerEdge) -> Option<Vec<Edge>> {
-        let source_power_node = self
-            .find_power_node(&power_edge.from)
-            .unwrap();
-        let target_power_node = self
-            .find_power_node(&power_edge.to)
-            .unwrap();
+        let source_power_node = self.find_power_node(&power_edge.from).unwrap();
+        let target_power_node = self.find_power_node(&power_edge.to).unwrap();
 
         console_debug!("{:?} ", power_edge);
         console_debug!("{:?} -> {:?}", source_power_node, target_power_node);
-        let target_items: Vec<&String> = target_power_node.cluster.items.iter().collect();
+        let target_items: Vec<&String> = target_power_node.named.items.iter().collect();
         let edges: Vec<Edge> = source_power_node
-            .cluster
+            .named
             .items
             .iter()
-            .cartesian_product(target_items.into_iter())
+            .cartesian_product(target_items)
             .map(|item| {
                 console_debug!("{:?}", item);
-                return Edge::new(item.0, item.1);
+                Edge::new(item.0, item.1)
             })
             .collect();
 
-        return Some(edges);
+        Some(edges)
     }
 
     /// For a given set of nodes, return all edges between the nodes.
-    fn subgraph(&self, nodes: &Set<NodeId>) -> Vec<Edge> {
+    fn subgraph(&self, nodes: &NodeSet) -> Vec<(NodeIndex, NodeIndex)> {
         self.edges.subgraph(nodes)
     }
 
-    fn neighbors(&self, node_id: &NodeId) -> Set<String> {
-        let parents = self.edges.parents(node_id);
-        let children = self.edges.children(node_id);
-
-        parents.union(&children)
-    }
-
-    fn predecessors(&self, node_id: &NodeId) -> Set<String> {
-        self.edges.parents(node_id)
-    }
-
     /// Use graph topology to identify cluster pairs for comparison.
-    fn generate_graph_comparison_set(
-        &self,
-        clusters: &Vec<Cluster>,
-    ) -> Vec<UnorderedTuple<Cluster>> {
-        let mut neighborhood_cluster_map: HashMap<String, Vec<Cluster>> = HashMap::new();
+    fn generate_graph_comparison_set(&self, clusters: &[Cluster]) -> Vec<UnorderedTuple<Cluster>> {
+        let mut neighborhood_cluster_map: BTreeMap<NodeIndex, Vec<Cluster>> = BTreeMap::new();
 
         console_debug!("Constructing neighborhood cluster map.");
         for cluster in clusters.iter() {
-            for neighbor in cluster.get_neighbors() {
-                if neighborhood_cluster_map.contains_key(&neighbor) {
-                    if let Some(clusters) = neighborhood_cluster_map.get_mut(&neighbor) {
-                        clusters.push(cluster.clone());
-                    }
-                } else {
-                    neighborhood_cluster_map.insert(neighbor, vec![cluster.clone()]);
-                }
+            for neighbor in cluster.get_neighbors().iter() {
+                neighborhood_cluster_map
+                    .entry(neighbor)
+                    .or_default()
+                    .push(cluster.clone());
             }
         }
         console_debug!(
@@ -309,387 +600,169 @@ impl PowerGraph {
         );
 
         console_debug!("Creating comparison set.");
-        return neighborhood_cluster_map
+        neighborhood_cluster_map
             .into_values()
             .flat_map(|cluster_ids| {
                 cluster_ids
                     .iter()
                     .combinations(2)
                     .map(|combination| UnorderedTuple {
-                        one: (*(combination.get(0).unwrap())).clone(),
+                        one: (*(combination.first().unwrap())).clone(),
                         two: (*(combination.get(1).unwrap())).clone(),
                     })
                     .collect::<Vec<UnorderedTuple<Cluster>>>()
             })
-            .collect();
+            .collect()
     }
 
-    #[wasm_bindgen]
-    pub fn decompose(&mut self) {
-        let mut cluster_repository = ClusterRepository::new();
-
-        // let mut c: Vec<Cluster> = Vec::new();
-
-        // Add all nodes to c and c_prime as singleton clusters.
-        console_log!("Identify singleton clusters.");
-        for node in (&self.nodes).into_iter() {
-            // println!("Node: {:?}", node);
-            // console_log!("Node: {:?}", node);
-
-            let cluster_nodes = Cluster::new(
-                Set::from_iter(vec![node.id.clone()]),
-                Set::from_iter(
-                    self.neighbors(&node.id)
-                        .to_vec()
-                        .into_iter()
-                        .map(|item| item.to_string())
-                        .collect(),
-                ),
-            );
-
-            cluster_repository.add_cluster(&cluster_nodes);
-        }
-        let mut c_prime = cluster_repository.clone();
-
-        console_log!(
-            "Singleton cluster identification complete. Found {:?} clusters.",
-            cluster_repository.len()
-        );
-
-        let mut similarity_matrix = SimilarityMatrix::new();
-
-        let comparison_sets =
-            // clusters::generate_comparison_set(&c_prime);
-            self.generate_graph_comparison_set(&c_prime.clone().clusters.into_values().collect());
-
-        console_log!(
-            "Identified {:?} sets of clusters for comparison.",
-            comparison_sets.len()
-        );
-
-        let mut cluster_map: HashMap<String, Cluster> = cluster_repository.clusters.clone();
-
-        for comparison_set in comparison_sets.to_vec() {
-            let cluster = comparison_set.one;
-
-            let comparison_cluster = comparison_set.two;
-
-            let similarity = cluster.similarity(&comparison_cluster);
-            similarity_matrix.set_similarity(
-                UnorderedTuple {
-                    one: cluster.get_id().to_string(),
-                    two: comparison_cluster.get_id().to_string(),
-                },
-                similarity,
-            );
-        }
-
-        // Find the two clusters with maximum similarity
-        let mut max_similarity_result = similarity_matrix.get_max_similarity();
-        console_debug!("{:?}", max_similarity_result);
-
-        const MINIMUM_SIMILARITY: f32 = 0.25_f32;
-
-        while c_prime.len() > 0 {
-            match max_similarity_result {
-                Some(_) => (),
-                None => break,
-            }
-
-            let max_similarity = max_similarity_result.unwrap();
-
-            if max_similarity.1 < MINIMUM_SIMILARITY {
-                break;
-            }
-
-            console_log!("Max similarity: {:?}", max_similarity);
-            console_log!("Clusters remaining to process: {:?}", c_prime.len());
-
-            let cluster = cluster_map.get(&max_similarity.0.one).unwrap();
-
-            let comparison_cluster = cluster_map.get(&max_similarity.0.two).unwrap();
-
-            // console_debug!("{:?} <-> {:?}", cluster, comparison_cluster);
-
-            c_prime.remove(&cluster.get_id().to_string());
-            c_prime.remove(&comparison_cluster.get_id().to_string());
-
-            similarity_matrix.remove_element(cluster.get_id().to_string());
-            similarity_matrix.remove_element(comparison_cluster.get_id().to_string());
-
-            let unioned_cluster = cluster.clone().union(&comparison_cluster);
+    /// Decompose the graph, calling `progress`, if given, with the name of each
+    /// phase, the items processed and the total as the decomposition runs.
+    /// Fails with a "cancelled" error, leaving no power nodes or power edges,
+    /// if `signal` is aborted, such as from within `progress`.
+    #[wasm_bindgen(js_name = decompose)]
+    pub fn decompose_cancellable(
+        &mut self,
+        progress: Option<js_sys::Function>,
+        signal: Option<AbortSignal>,
+    ) -> Result<(), JsError> {
+        self.decompose_with_options_cancellable(&DecompositionOptions::default(), progress, signal)
+    }
 
-            // Add new cluster to everything!
-            cluster_repository.add_cluster(&unioned_cluster);
-            c_prime.add_cluster(&unioned_cluster);
-            cluster_map.insert(unioned_cluster.get_id().to_string(), unioned_cluster.clone());
+    /// Decompose the graph as with `decompose`, using the given options in place
+    /// of the defaults.
+    #[wasm_bindgen(js_name = decompose_with_options)]
+    pub fn decompose_with_options_cancellable(
+        &mut self,
+        options: &DecompositionOptions,
+        progress: Option<js_sys::Function>,
+        signal: Option<AbortSignal>,
+    ) -> Result<(), JsError> {
+        let mut progress = JsProgress {
+            callback: progress,
+            signal,
+        };
+        Ok(self.decompose_with_progress(options, &mut progress)?)
+    }
 
-            // Calculate new similarities for the added element.
+    fn decompose_graph(
+        &mut self,
+        options: &DecompositionOptions,
+        similarity: &dyn Similarity,
+        progress: &mut dyn Progress,
+    ) -> Result<(), PowerGraphError> {
+        options.validate()?;
 
-            // let cluster_parents = Set::from_iter(unioned_cluster.get_neighbors());
-            for comparison_cluster in c_prime.get_sibling_clusters(&unioned_cluster).iter() {
-                if unioned_cluster == *comparison_cluster {
-                    continue;
-                }
+        let mut state = DecompositionState::new();
+        state.step(self, options, similarity, progress, usize::MAX)?;
 
-                // let comparison_cluster_parents = Set::from_iter(comparison_cluster.get_neighbors());
+        Ok(())
+    }
 
-                let similarity = unioned_cluster.similarity(&comparison_cluster);
-                similarity_matrix.set_similarity(
-                    UnorderedTuple {
-                        one: unioned_cluster.get_id().to_string(),
-                        two: comparison_cluster.get_id().to_string(),
-                    },
-                    similarity,
-                );
-            }
+    /// Sum the test counts of the nodes within each power node.
+    fn aggregate_test_counts(&mut self) {
+        let test_counts: HashMap<&NodeId, usize> = self
+            .nodes
+            .iter()
+            .map(|node| (&node.id, node.test_count))
+            .collect();
 
-            // console_debug!("{:?}", similarity_matrix);
-            max_similarity_result = similarity_matrix.get_max_similarity();
+        for power_node in self.power_nodes.iter_mut() {
+            power_node.test_count = power_node
+                .named
+                .items
+                .iter()
+                .filter_map(|item| test_counts.get(item))
+                .sum();
         }
+    }
 
-        // Add first and second order neighborhoods as clusters in `c`.
+    /// Collect the projects of the nodes within each power node.
+    fn aggregate_projects(&mut self) {
+        let projects: HashMap<&NodeId, &String> = self
+            .nodes
+            .iter()
+            .filter_map(|node| node.project.as_ref().map(|project| (&node.id, project)))
+            .collect();
 
-        let cluster_keys: Vec<String> = cluster_repository.clusters.keys().cloned().collect();
-        for cluster_key in &cluster_keys {
-            let cluster = cluster_repository.clusters.get(cluster_key).unwrap().clone();
-            let items = cluster.get_neighbors();
-            let neighbors = items
+        for power_node in self.power_nodes.iter_mut() {
+            power_node.projects = power_node
+                .named
+                .items
                 .iter()
-                .map(|node| self.neighbors(node))
-                .fold(Set::new(), |acc: Set<String>, e| acc.union(&e));
-            let neighborhood_cluster = Cluster::new(Set::from_iter(items), neighbors);
-
-            let neighbor_similarity = cluster.similarity(&neighborhood_cluster);
-
-            if neighbor_similarity >= MINIMUM_SIMILARITY {
-                console_log!(
-                    "The similarity between {:?} and {:?} is {:?}. Adding to `c`.",
-                    cluster,
-                    neighborhood_cluster,
-                    neighbor_similarity
-                );
-
-                cluster_repository.add_cluster(&neighborhood_cluster);
-            }
+                .filter_map(|item| projects.get(item))
+                .map(|project| project.to_string())
+                .unique()
+                .sorted()
+                .collect();
         }
+    }
 
-        // Do it again for second-order neighbors.
-        let cluster_keys: Vec<String> = cluster_repository.clusters.keys().cloned().collect();
-        for cluster_key in &cluster_keys {
-            let cluster = cluster_repository.clusters.get(cluster_key).unwrap().clone();
-            let items = cluster.get_neighbors();
-            let neighbors = items
-                .iter()
-                .map(|node| self.neighbors(node))
-                .fold(Set::new(), |acc: Set<String>, e| acc.union(&e));
-            let neighborhood_cluster = Cluster::new(Set::from_iter(items), neighbors);
-
-            let neighbor_similarity = cluster.similarity(&neighborhood_cluster);
-
-            if neighbor_similarity >= MINIMUM_SIMILARITY {
-                cluster_repository.add_cluster(&neighborhood_cluster);
-            }
+    /// Link each power node to the smallest power node strictly containing it,
+    /// and to the power nodes it contains. Power nodes are only a tree if they
+    /// are laminar, which `verify` checks.
+    fn aggregate_containment(&mut self) {
+        let mut order: Vec<usize> = (0..self.power_nodes.len()).collect();
+        order.sort_by(|&a, &b| {
+            let (a, b) = (&self.power_nodes[a], &self.power_nodes[b]);
+            b.cluster
+                .size()
+                .cmp(&a.cluster.size())
+                .then_with(|| a.id.cmp(&b.id))
+        });
+
+        // Power nodes are visited largest first, so the last containing power
+        // node seen is the smallest one, and its depth is already known.
+        let mut parents: Vec<Option<usize>> = vec![None; self.power_nodes.len()];
+        for (position, &index) in order.iter().enumerate() {
+            let items = &self.power_nodes[index].cluster.items;
+            parents[index] = order[..position].iter().rev().copied().find(|&candidate| {
+                items.is_proper_subset_of(&self.power_nodes[candidate].cluster.items)
+            });
+
+            let depth = parents[index].map_or(0, |parent| self.power_nodes[parent].depth + 1);
+            self.power_nodes[index].depth = depth;
         }
 
-        let singletons: Vec<PowerNode> = cluster_repository
-            .clusters
-            .values()
-            .filter(|cluster| cluster.size() <= 1)
-            .map(|cluster| {
-                console_debug!(
-                    "{:?} is a singleton. Adding to PowerNodes.",
-                    cluster.get_id()
-                );
-                PowerNode {
-                    id: cluster.get_id().to_string(),
-                    cluster: cluster.clone(),
-                }
-            })
-            .collect();
-        self.power_nodes.extend(singletons);
-
-        // Generate candidates for PowerEdges
-        let mut edge_candidates: BinaryHeap<PowerEdgeCandidate> = BinaryHeap::new();
-        let mut queued_candidates: HashSet<PowerEdgeCandidate> = HashSet::new();
-
-        // console_log!("Generating pairs of clusters to evaluate.",);
-
-        // let cluster_pairs: Set<UnorderedTuple<&Cluster>> = Set::from_iter(
-
-        //         .collect(),
-        // );
-
-        // console_log!("{:?} pairs of clusters identified.", cluster_pairs.len());
-
-        // TODO: This is probably an area for a performance improvement.
-        // for cluster_pair in self.generate_graph_comparison_set(&self.clusters) {
-        console_log!("Checking cluster pairs for poweredge candidates");
-        let combinations: Vec<UnorderedTuple<&Cluster>> = cluster_repository
-            .clusters
-            .values()
-            .combinations_with_replacement(2)
-            .map(|cluster| UnorderedTuple {
-                one: *(cluster.get(0).unwrap()),
-                two: *(cluster.get(1).unwrap()),
-            })
-            .collect();
-
-        let combination_count = combinations.len();
-        console_log!(
-            "{:?} combinations for power edge candidates found. Evaluating.",
-            combination_count
-        );
-
-        // #[cfg(not(target_arch = "wasm32"))]
-        // {
-        //     let mut time_chunk_start_time = Instant::now();
-        //     let mut count = 0;
-        // }
-        for cluster_pair in combinations.iter() {
-            // #[cfg(not(target_arch = "wasm32"))]
-            // {
-            //     count += 1;
-
-            //     if time_chunk_start_time.elapsed().as_secs() >= 10 {
-            //         let rate = count as f32 / time_chunk_start_time.elapsed().as_secs() as f32;
-            //         console_log!(
-            //         "Checking combination {:?} of {:?}. Speed: {:?} combinations / second. Time remaining: {:?}",
-            //         index,
-            //         combination_count,
-            //         rate,
-            //         (combination_count - index) as f32 / rate
-            //     );
-            //         time_chunk_start_time = Instant::now();
-            //         count = 0;
-            //     }
-            // }
-
-            let cluster_one = cluster_pair.one;
-            let cluster_two = cluster_pair.two;
-
-            let node_intersection = cluster_one.items.intersection(&cluster_two.items);
-            let node_union = cluster_one.items.union(&cluster_two.items);
-
-            if node_intersection.len() == 0
-                && self.clusters_create_subgraph(&cluster_one, &cluster_two)
-            {
-                // console_debug!(
-                //     "  a non-intersecting candidate between {:?} and {:?}.",
-                //     cluster_one.get_id(),
-                //     cluster_two.get_id()
-                // );
-
-                let edges = self.subgraph(&node_union);
-
-                let candidate = PowerEdgeCandidate {
-                    from: cluster_one.clone(),
-                    to: cluster_two.clone(),
-                    size: edges.len() as f32,
-                };
-                queued_candidates.insert(candidate.clone());
-                edge_candidates.push(candidate);
-            }
-
-            if cluster_one == cluster_two && self.clusters_are_clique(&cluster_one, &cluster_two) {
-                // console_debug!(
-                //     "There is a clique candidate between {:?} and {:?}.",
-                //     cluster_one.get_id(),
-                //     cluster_two.get_id()
-                // );
-
-                let edges = self.subgraph(&node_union);
-                let candidate = PowerEdgeCandidate {
-                    from: cluster_one.clone(),
-                    to: cluster_two.clone(),
-                    size: edges.len() as f32 / 2_f32,
-                };
-                queued_candidates.insert(candidate.clone());
-                edge_candidates.push(candidate);
+        let mut children: Vec<Vec<PowerNodeId>> = vec![Vec::new(); self.power_nodes.len()];
+        for (index, parent) in parents.iter().enumerate() {
+            if let Some(parent) = parent {
+                children[*parent].push(self.power_nodes[index].id.clone());
             }
         }
 
-        // console_debug!("PowerEdge Candidates: {:?}", edge_candidates);
-
-        let mut completed_candidates: HashSet<PowerEdgeCandidate> = HashSet::new();
-
-        while let Some(edge_candidate) = edge_candidates.pop() {
-            let candidate_processor_results =
-                self.process_edge_candidate(&edge_candidate, &cluster_repository);
-
-            for result in candidate_processor_results {
-                match result {
-                    PowerEdgeCandidateProcessorOutput::NewPowerEdgeCandidate(candidate) => {
-                        // console_log!("Power Edge Candidate found: {:?}", candidate);
-
-                        // Don't process the same edge candidate twice.
-                        if completed_candidates.contains(&candidate) {
-                            continue;
-                        }
-
-                        // Don't process invalid edges
-                        if candidate.from.get_id().is_empty() || candidate.to.get_id().is_empty() {
-                            continue;
-                        }
-
-                        cluster_repository.add_cluster(&candidate.from);
-                        cluster_repository.add_cluster(&candidate.to);
-
-                        if !queued_candidates.contains(&candidate) {
-                            queued_candidates.insert(candidate.clone());
-                            edge_candidates.push(candidate.clone());
-                        }
-                        completed_candidates.insert(candidate);
-                    }
-                    PowerEdgeCandidateProcessorOutput::NewPowerNode(power_node) => {
-                        // console_log!("Power Node found: {:?}", power_node);
-                        cluster_repository.add_cluster(&power_node.cluster);
-
-                        self.power_nodes.push(power_node);
-                    }
-                    PowerEdgeCandidateProcessorOutput::NewPowerEdge(power_edge) => {
-                        // console_log!("Power Edge found: {:?}", power_edge);
-
-                        self.power_edges.push(power_edge)
-                    }
-                }
-            }
-            console_log!("Candidate Count: {:?}", edge_candidates.len());
+        for (index, children) in children.into_iter().enumerate() {
+            let parent = parents[index].map(|parent| self.power_nodes[parent].id.clone());
+            let power_node = &mut self.power_nodes[index];
+            power_node.parent = parent;
+            power_node.children = children.into_iter().sorted().collect();
         }
+    }
 
-        // For all remaining edges not yet covered by power edges, create new power edges.
-        console_debug!("PowerEdges: {:?}", self.power_edges);
-        let mut covered_edges: HashSet<(String, String)> = HashSet::new();
-        for power_edge in self.power_edges.iter() {
-            if let Some(edges) = self.expand_power_edge(power_edge) {
-                for edge in edges {
-                    covered_edges.insert((edge.from.clone(), edge.to.clone()));
-                    covered_edges.insert((edge.to, edge.from));
-                }
-            }
-        }
+    /// Find the attributes shared by the nodes within each power node.
+    fn aggregate_attributes(&mut self) {
+        let attributes: HashMap<&NodeId, &NodeAttributes> = self
+            .nodes
+            .iter()
+            .map(|node| (&node.id, &node.attributes))
+            .collect();
 
-        console_debug!("Covered edges: {:?}", covered_edges);
-        for edge in self.edges.clone().into_iter() {
-            if !covered_edges.contains(&(edge.from.clone(), edge.to.clone())) {
-                self.power_edges.push(PowerEdge {
-                    from: edge.from,
-                    to: edge.to,
-                })
-            }
+        for power_node in self.power_nodes.iter_mut() {
+            power_node.attributes = NodeAttributes::shared(
+                power_node
+                    .named
+                    .items
+                    .iter()
+                    .filter_map(|item| attributes.get(item).copied()),
+            );
         }
-
-        console_log!(
-            "Complete!\nPower Nodes: {:?}\n\tPower Edges: {:?}",
-            self.power_nodes,
-            self.power_edges
-        );
     }
 
     fn process_edge_candidate(
         &self,
         edge_candidate: &PowerEdgeCandidate,
         cluster_repository: &ClusterRepository,
+        edge_coverage: &EdgeCoverage,
+        options: &DecompositionOptions,
     ) -> Vec<PowerEdgeCandidateProcessorOutput> {
         if edge_candidate.size <= 2.0 && edge_candidate.from == edge_candidate.to {
             return vec![];
@@ -699,12 +772,7 @@ impl PowerGraph {
 
         // Is there an existing powernode that overlaps with the source of the powernode
         // that is not a perfect superset?
-        for power_node in self.power_nodes.iter() {
-            let u_s_intersection = edge_candidate
-                .from
-                .items
-                .intersection(&power_node.cluster.items);
-
+        let overlapping_source = parallel::find_first(&self.power_nodes, |power_node| {
             let s_subset_u = edge_candidate
                 .from
                 .items
@@ -714,65 +782,60 @@ impl PowerGraph {
                 .items
                 .is_subset_of(&edge_candidate.from.items);
 
-            if u_s_intersection.len() > 0 && !s_subset_u && !u_subset_s {
-                console_debug!(
-                    "Creating a new PowerEdgeCandidate.\n\tCluster U: {:?}\n\tCluster S: {:?}",
-                    edge_candidate.from,
-                    power_node.cluster
-                );
-
-                // console_debug!("Checking intersection: {:?}.", u_s_intersection);
-
-                // console_debug!(
-                //     "U - S: {:?} ",
-                //     edge_candidate
-                //         .from
-                //         .items
-                //         .difference(&power_node.cluster.items)
-                // );
-
-                // console_debug!(
-                //     "S - U: {:?}.",
-                //     power_node
-                //         .cluster
-                //         .items
-                //         .difference(&edge_candidate.from.items)
-                // );
-
-                let difference_cluster =
-                    edge_candidate.from.clone().difference(&power_node.cluster);
-                let intersection_cluster = edge_candidate
-                    .from
-                    .clone()
-                    .intersection(&power_node.cluster);
-
-                return vec![
-                    PowerEdgeCandidateProcessorOutput::NewPowerEdgeCandidate(PowerEdgeCandidate {
-                        from: difference_cluster.clone(),
-                        to: edge_candidate.to.clone(),
-                        size: (difference_cluster.size() + edge_candidate.to.size()) as f32,
-                    }),
-                    PowerEdgeCandidateProcessorOutput::NewPowerEdgeCandidate(PowerEdgeCandidate {
-                        from: intersection_cluster.clone(),
-                        to: edge_candidate.to.clone(),
-                        size: (intersection_cluster.size() + edge_candidate.to.size()) as f32,
-                    }),
-                ];
-            }
-        }
-        // Is there an existing powernode that overlaps with the source of the powernode
-        // that is not a perfect superset?
-        for power_node in self.power_nodes.iter() {
-            let w_s_intersection = edge_candidate
-                .to
+            !edge_candidate
+                .from
                 .items
-                .intersection(&power_node.cluster.items);
+                .is_disjoint(&power_node.cluster.items)
+                && !s_subset_u
+                && !u_subset_s
+        });
+        if let Some(power_node) = overlapping_source {
+            console_debug!(
+                "Creating a new PowerEdgeCandidate.\n\tCluster U: {:?}\n\tCluster S: {:?}",
+                edge_candidate.from,
+                power_node.cluster
+            );
+
+            // console_debug!("Checking intersection: {:?}.", u_s_intersection);
+
             // console_debug!(
-            //     "w_s_intersection with {:?}: {:?}",
-            //     power_node,
-            //     w_s_intersection
+            //     "U - S: {:?} ",
+            //     edge_candidate
+            //         .from
+            //         .items
+            //         .difference(&power_node.cluster.items)
             // );
 
+            // console_debug!(
+            //     "S - U: {:?}.",
+            //     power_node
+            //         .cluster
+            //         .items
+            //         .difference(&edge_candidate.from.items)
+            // );
+
+            let difference_cluster = edge_candidate.from.clone().difference(&power_node.cluster);
+            let intersection_cluster = edge_candidate
+                .from
+                .clone()
+                .intersection(&power_node.cluster);
+
+            return vec![
+                PowerEdgeCandidateProcessorOutput::NewPowerEdgeCandidate(PowerEdgeCandidate {
+                    from: difference_cluster.clone(),
+                    to: edge_candidate.to.clone(),
+                    size: (difference_cluster.size() + edge_candidate.to.size()) as f32,
+                }),
+                PowerEdgeCandidateProcessorOutput::NewPowerEdgeCandidate(PowerEdgeCandidate {
+                    from: intersection_cluster.clone(),
+                    to: edge_candidate.to.clone(),
+                    size: (intersection_cluster.size() + edge_candidate.to.size()) as f32,
+                }),
+            ];
+        }
+        // Is there an existing powernode that overlaps with the source of the powernode
+        // that is not a perfect superset?
+        let overlapping_target = parallel::find_first(&self.power_nodes, |power_node| {
             let s_subset_w = edge_candidate
                 .to
                 .items
@@ -782,103 +845,97 @@ impl PowerGraph {
                 .items
                 .is_proper_subset_of(&edge_candidate.to.items);
 
-            // console_debug!("s_subset_w: {:?}, w_subset_s: {:?}", s_subset_w, w_subset_s);
+            !edge_candidate
+                .to
+                .items
+                .is_disjoint(&power_node.cluster.items)
+                && !s_subset_w
+                && !w_subset_s
+        });
+        if let Some(power_node) = overlapping_target {
+            console_debug!(
+                "Creating a new PowerEdgeCandidate.\n\tCluster W: {:?}\n\tCluster S: {:?}",
+                edge_candidate.to,
+                power_node.cluster
+            );
 
-            if w_s_intersection.len() > 0 && !s_subset_w && !w_subset_s {
-                console_debug!(
-                    "Creating a new PowerEdgeCandidate.\n\tCluster W: {:?}\n\tCluster S: {:?}",
-                    edge_candidate.to,
-                    power_node.cluster
-                );
+            console_debug!(
+                "Checking intersection: {:?}.",
+                edge_candidate
+                    .to
+                    .items
+                    .intersection(&power_node.cluster.items)
+            );
 
-                console_debug!("Checking intersection: {:?}.", w_s_intersection);
-
-                // console_debug!(
-                //     "U - S: {:?} ",
-                //     edge_candidate
-                //         .to
-                //         .items
-                //         .difference(&power_node.cluster.items)
-                // );
-
-                // console_debug!(
-                //     "S - U: {:?}.",
-                //     power_node
-                //         .cluster
-                //         .items
-                //         .difference(&edge_candidate.to.items)
-                // );
-
-                let difference_cluster = edge_candidate.to.clone().difference(&power_node.cluster);
-                let intersection_cluster =
-                    edge_candidate.to.clone().intersection(&power_node.cluster);
-
-                return vec![
-                    PowerEdgeCandidateProcessorOutput::NewPowerEdgeCandidate(PowerEdgeCandidate {
-                        from: edge_candidate.from.clone(),
-                        to: difference_cluster.clone(),
-                        size: (difference_cluster.size() + edge_candidate.from.size()) as f32,
-                    }),
-                    PowerEdgeCandidateProcessorOutput::NewPowerEdgeCandidate(PowerEdgeCandidate {
-                        from: edge_candidate.from.clone(),
-                        to: intersection_cluster.clone(),
-                        size: (intersection_cluster.size() + edge_candidate.from.size()) as f32,
-                    }),
-                ];
-            }
+            // console_debug!(
+            //     "U - S: {:?} ",
+            //     edge_candidate
+            //         .to
+            //         .items
+            //         .difference(&power_node.cluster.items)
+            // );
+
+            // console_debug!(
+            //     "S - U: {:?}.",
+            //     power_node
+            //         .cluster
+            //         .items
+            //         .difference(&edge_candidate.to.items)
+            // );
+
+            let difference_cluster = edge_candidate.to.clone().difference(&power_node.cluster);
+            let intersection_cluster = edge_candidate.to.clone().intersection(&power_node.cluster);
+
+            return vec![
+                PowerEdgeCandidateProcessorOutput::NewPowerEdgeCandidate(PowerEdgeCandidate {
+                    from: edge_candidate.from.clone(),
+                    to: difference_cluster.clone(),
+                    size: (difference_cluster.size() + edge_candidate.from.size()) as f32,
+                }),
+                PowerEdgeCandidateProcessorOutput::NewPowerEdgeCandidate(PowerEdgeCandidate {
+                    from: edge_candidate.from.clone(),
+                    to: intersection_cluster.clone(),
+                    size: (intersection_cluster.size() + edge_candidate.from.size()) as f32,
+                }),
+            ];
         }
 
         // let cluster_subgraph = self.subgraph(&power_edge_nodes);
 
-        // Hoist candidate-only computation outside the filter loop.
+        // Hoist candidate-only computation outside the loop.
         let candidate_union = edge_candidate.from.clone().union(&edge_candidate.to);
-        let candidate_subgraph = Set::from_iter(self.subgraph(&candidate_union.items));
+        let candidate_subgraph = self.subgraph(&candidate_union.items);
 
-        let overlapping_power_edges: Vec<&PowerEdge> = self
-            .power_edges
-            .iter()
-            .filter(|power_edge| {
-                let s = match cluster_repository.get(&power_edge.from){
+        // The power edges whose nodes share an edge with (U ∪ W), by index, with
+        // the number of edges they share.
+        let overlapping_power_edges = edge_coverage.overlapping(&candidate_subgraph);
+
+        console_debug!("Overlapping power edges: {:?}", overlapping_power_edges);
+
+        if !overlapping_power_edges.is_empty() {
+            for (index, shared_edges) in overlapping_power_edges {
+                let power_edge = &self.power_edges[index];
+                let s = match self.power_edge_cluster(&power_edge.from, cluster_repository) {
                     Some(cluster) => cluster,
                     None => {
                         panic!("Attempted to load the cluster {:?}, from the cluster map, but it was not found. Skipping check.", power_edge.from);
                     }
                 };
-                let t = match cluster_repository.get(&power_edge.to){
+                let t = match self.power_edge_cluster(&power_edge.to, cluster_repository) {
                     Some(cluster) => cluster,
                     None => {
                         panic!("Attempted to load the cluster {:?}, from the cluster map, but it was not found. Skipping check.", power_edge.to);
                     }
                 };
 
-                // Check if (UxW) intersects with (SxT).
-                let comparison_union = s.clone().union(&t);
-                let comparison_subgraph = Set::from_iter(self.subgraph(&comparison_union.items));
-
-                let edge_intersection = candidate_subgraph.intersection(&comparison_subgraph);
-
-                return edge_intersection.len() > 0;
-            })
-            .collect();
-
-        console_debug!("Overlapping power edges: {:?}", overlapping_power_edges);
-
-        if overlapping_power_edges.len() > 0 {
-            for power_edge in overlapping_power_edges {
                 // If (S, T) covers not all edges of (U, W): ((U × W) ⊄ (S × T)):
-
-                let s = cluster_repository.get(&power_edge.from).unwrap();
-                let t = cluster_repository.get(&power_edge.to).unwrap();
-
-                let comparison_union = s.clone().union(&t);
-                let comparison_subgraph = Set::from_iter(self.subgraph(&comparison_union.items));
-
-                let covers_all_edges = candidate_subgraph.is_proper_subset_of(&comparison_subgraph);
+                let covers_all_edges = shared_edges == candidate_subgraph.len()
+                    && edge_coverage.size(index) > candidate_subgraph.len();
 
                 console_debug!(
-                    "Is {:?} a subset of {:?}? {:?}",
-                    candidate_subgraph,
-                    comparison_subgraph,
+                    "Does {:?} cover all {:?} edges of the candidate? {:?}",
+                    power_edge,
+                    candidate_subgraph.len(),
                     covers_all_edges
                 );
 
@@ -889,7 +946,7 @@ impl PowerGraph {
                             edge_candidate.from,
                             s, t
                         );
-                        let target_cluster = edge_candidate.to.clone().difference(&t);
+                        let target_cluster = edge_candidate.to.clone().difference(t);
 
                         return vec![PowerEdgeCandidateProcessorOutput::NewPowerEdgeCandidate(
                             PowerEdgeCandidate {
@@ -905,7 +962,7 @@ impl PowerGraph {
                             t, s
                         );
 
-                        let target_cluster = edge_candidate.to.clone().difference(&s);
+                        let target_cluster = edge_candidate.to.clone().difference(s);
 
                         return vec![PowerEdgeCandidateProcessorOutput::NewPowerEdgeCandidate(
                             PowerEdgeCandidate {
@@ -921,7 +978,7 @@ impl PowerGraph {
                             s
                         );
 
-                        let source_cluster = edge_candidate.from.clone().difference(&t);
+                        let source_cluster = edge_candidate.from.clone().difference(t);
 
                         return vec![PowerEdgeCandidateProcessorOutput::NewPowerEdgeCandidate(
                             PowerEdgeCandidate {
@@ -937,7 +994,7 @@ impl PowerGraph {
                             t
                         );
 
-                        let source_cluster = edge_candidate.from.clone().difference(&s);
+                        let source_cluster = edge_candidate.from.clone().difference(s);
 
                         return vec![PowerEdgeCandidateProcessorOutput::NewPowerEdgeCandidate(
                             PowerEdgeCandidate {
@@ -953,87 +1010,443 @@ impl PowerGraph {
         }
 
         if edge_candidate.to == edge_candidate.from {
-            let id = edge_candidate.to.get_id().to_string();
+            let id = edge_candidate.to.get_id(&self.interner);
             return vec![
-                PowerEdgeCandidateProcessorOutput::NewPowerNode(PowerNode {
-                    id: id.clone(),
-                    cluster: edge_candidate.to.clone(),
-                }),
+                PowerEdgeCandidateProcessorOutput::NewPowerNode(Box::new(PowerNode::new(
+                    edge_candidate.to.clone(),
+                    &self.interner,
+                ))),
                 PowerEdgeCandidateProcessorOutput::NewPowerEdge(PowerEdge {
                     from: id.clone(),
                     to: id,
+                    residual: false,
+                    negative_edges: Vec::new(),
                 }),
             ];
         }
 
+        // In lossy mode, the candidate may be missing edges. Record them, unless
+        // splitting the candidate has left too many of them.
+        let negative_edges = if options.is_lossy() {
+            let negative_edges =
+                self.missing_edges(&edge_candidate.from, &edge_candidate.to, options.directed);
+            if !options.allows_missing(
+                negative_edges.len(),
+                edge_candidate.from.size() * edge_candidate.to.size(),
+            ) {
+                return vec![];
+            }
+            negative_edges
+        } else {
+            Vec::new()
+        };
+
         // Otherwise, add power nodes for `from` and `to`, and a power edge between them.
-        let from_id = edge_candidate.from.get_id().to_string();
-        let to_id = edge_candidate.to.get_id().to_string();
-        return vec![
-            PowerEdgeCandidateProcessorOutput::NewPowerNode(PowerNode {
-                id: from_id.clone(),
-                cluster: edge_candidate.from.clone(),
-            }),
-            PowerEdgeCandidateProcessorOutput::NewPowerNode(PowerNode {
-                id: to_id.clone(),
-                cluster: edge_candidate.to.clone(),
-            }),
+        let from_id = edge_candidate.from.get_id(&self.interner);
+        let to_id = edge_candidate.to.get_id(&self.interner);
+        vec![
+            PowerEdgeCandidateProcessorOutput::NewPowerNode(Box::new(PowerNode::new(
+                edge_candidate.from.clone(),
+                &self.interner,
+            ))),
+            PowerEdgeCandidateProcessorOutput::NewPowerNode(Box::new(PowerNode::new(
+                edge_candidate.to.clone(),
+                &self.interner,
+            ))),
             PowerEdgeCandidateProcessorOutput::NewPowerEdge(PowerEdge {
                 from: from_id,
                 to: to_id,
+                residual: false,
+                negative_edges,
             }),
-        ];
+        ]
     }
 
     fn clusters_create_subgraph(&self, cluster_one: &Cluster, cluster_two: &Cluster) -> bool {
         for u in cluster_one.items.iter() {
             for w in cluster_two.items.iter() {
-                match self.get_edge(&u, &w) {
-                    Some(_edge) => continue,
-                    _ => (),
+                if self.edges.has_edge(u, w) {
+                    continue;
                 }
 
-                match self.get_edge(&w, &u) {
-                    Some(_edge) => continue,
-                    _ => (),
+                if self.edges.has_edge(w, u) {
+                    continue;
                 }
 
                 return false;
             }
         }
 
-        return true;
+        true
+    }
+
+    /// Whether `from` and `to` form a biclique, allowing for the edges a lossy
+    /// decomposition may leave out.
+    fn clusters_form_biclique(
+        &self,
+        from: &Cluster,
+        to: &Cluster,
+        options: &DecompositionOptions,
+    ) -> bool {
+        if options.is_lossy() {
+            let missing = self.missing_edges(from, to, options.directed).len();
+            options.allows_missing(missing, from.size() * to.size())
+        } else if options.directed {
+            self.clusters_create_directed_subgraph(from, to)
+        } else {
+            self.clusters_create_subgraph(from, to)
+        }
+    }
+
+    /// The pairs of nodes in `from` and `to` without an edge from the first to
+    /// the second, or in either direction unless `directed`.
+    fn missing_edges(&self, from: &Cluster, to: &Cluster, directed: bool) -> Vec<Edge> {
+        from.items
+            .iter()
+            .cartesian_product(to.items.iter())
+            .filter(|(u, w)| {
+                !self.edges.has_edge(*u, *w) && (directed || !self.edges.has_edge(*w, *u))
+            })
+            .map(|(u, w)| Edge::new(self.interner.name(u), self.interner.name(w)))
+            .collect()
+    }
+
+    /// Whether every node of `from` has an edge to every node of `to`.
+    fn clusters_create_directed_subgraph(&self, from: &Cluster, to: &Cluster) -> bool {
+        from.items
+            .iter()
+            .all(|u| to.items.is_subset_of(self.edges.children(u)))
     }
 
-    fn clusters_are_clique(&self, cluster_one: &Cluster, cluster_two: &Cluster) -> bool {
+    fn clusters_are_clique(
+        &self,
+        cluster_one: &Cluster,
+        cluster_two: &Cluster,
+        directed: bool,
+    ) -> bool {
         for u in cluster_one.items.iter() {
             for w in cluster_two.items.iter() {
                 if u == w {
                     continue;
                 }
 
-                match self.get_edge(&u, &w) {
-                    Some(_edge) => continue,
-                    _ => (),
+                if self.edges.has_edge(u, w) {
+                    continue;
                 }
 
-                match self.get_edge(&w, &u) {
-                    Some(_edge) => continue,
-                    _ => (),
+                if !directed && self.edges.has_edge(w, u) {
+                    continue;
                 }
 
                 return false;
             }
         }
 
-        return true;
+        true
+    }
+}
+
+impl PowerGraph {
+    /// The edges implied by the power edges, sorted and without duplicates.
+    /// Nodes are not connected to themselves by power edges, so self-loops
+    /// only come from residual edges. In a directed decomposition these are
+    /// exactly the edges of the original graph.
+    pub fn expanded_edges(&self) -> Vec<Edge> {
+        self.power_edges
+            .iter()
+            .flat_map(|power_edge| self.implied_edges(power_edge))
+            .sorted_by(|a, b| (&a.from, &a.to).cmp(&(&b.from, &b.to)))
+            .dedup()
+            .collect()
+    }
+
+    /// The edges implied by a single power edge, without self-loops unless it
+    /// is a residual edge.
+    fn implied_edges(&self, power_edge: &PowerEdge) -> Vec<Edge> {
+        self.expand_power_edge(power_edge)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|edge| power_edge.residual || edge.from != edge.to)
+            .filter(|edge| !power_edge.negative_edges.contains(edge))
+            .collect()
+    }
+
+    pub fn decompose(&mut self) {
+        self.decompose_with_options(&DecompositionOptions::default())
+            .expect("the default options are valid")
+    }
+
+    /// Decompose the graph as with `decompose`, using the given options in place
+    /// of the defaults. Fails with `PowerGraphError::InvalidOptions` if the
+    /// options are out of range.
+    pub fn decompose_with_options(
+        &mut self,
+        options: &DecompositionOptions,
+    ) -> Result<(), PowerGraphError> {
+        self.decompose_with_similarity(options, &options.similarity)
+    }
+
+    /// Decompose the graph as with `decompose_with_options`, comparing clusters
+    /// with a custom similarity measure in place of `options.similarity`.
+    pub fn decompose_with_similarity(
+        &mut self,
+        options: &DecompositionOptions,
+        similarity: &dyn Similarity,
+    ) -> Result<(), PowerGraphError> {
+        // Decompositions without progress are never cancelled, so the only
+        // failure is invalid options.
+        self.decompose_graph(options, similarity, &mut NoProgress)
+    }
+
+    /// Decompose the graph as with `decompose_with_options`, reporting progress
+    /// to `progress` and stopping with `PowerGraphError::Cancelled`, leaving no
+    /// power nodes or power edges, once it is cancelled.
+    pub fn decompose_with_progress(
+        &mut self,
+        options: &DecompositionOptions,
+        progress: &mut dyn Progress,
+    ) -> Result<(), PowerGraphError> {
+        let result = self.decompose_graph(options, &options.similarity, progress);
+        if result.is_err() {
+            self.set_power_nodes(Vec::new());
+            self.power_edges.clear();
+            self.timings.clear();
+        }
+        result
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashSet;
+
     use super::*;
 
+    /// The decomposition of `assets/manifest.small.json`, regenerated with
+    /// `powergraph decompose ../../../assets/manifest.small.json > tests/golden/small_manifest.powergraph.json`
+    /// when the decomposition is meant to change.
+    const GOLDEN_MANIFEST_SMALL: &str =
+        include_str!("../tests/golden/small_manifest.powergraph.json");
+
+    fn decompose_manifest_small() -> String {
+        let manifest: manifest::Manifest = include_str!("../../../../assets/manifest.small.json")
+            .parse()
+            .unwrap();
+        let (nodes, edges) = manifest.to_graph().unwrap();
+        let mut powergraph = PowerGraph::new(nodes, edges);
+        powergraph.decompose();
+        serde_json::to_string(&powergraph).unwrap()
+    }
+
+    /// A graph of `count` connected components, each a biclique of differing
+    /// size with a tail.
+    fn components_graph(count: usize) -> PowerGraph {
+        let mut nodes = vec![];
+        let mut edges = vec![];
+        for component in 0..count {
+            let mut node = |name: String| {
+                let id = format!("c{component}_{name}");
+                nodes.push(Node::new(id.clone(), "{}".to_string()));
+                id
+            };
+            let sources: Vec<String> = (0..1 + component % 3)
+                .map(|source| node(format!("s{source}")))
+                .collect();
+            let targets: Vec<String> = (0..2 + component % 2)
+                .map(|target| node(format!("t{target}")))
+                .collect();
+            let tail = node("u".to_string());
+
+            for source in sources.iter() {
+                for target in targets.iter() {
+                    edges.push(Edge::new(source, target));
+                }
+            }
+            edges.push(Edge::new(&targets[0], &tail));
+        }
+        PowerGraph::new(nodes, edges)
+    }
+
+    /// The decomposition of `components_graph(24)`, regenerated from
+    /// `decompose_components` when the decomposition is meant to change.
+    const GOLDEN_COMPONENTS: &str = include_str!("../tests/golden/components.powergraph.json");
+
+    fn decompose_components() -> String {
+        let mut powergraph = components_graph(24);
+        powergraph.decompose();
+        serde_json::to_string(&powergraph).unwrap()
+    }
+
+    #[test]
+    fn decompose_deterministically() {
+        let first = decompose_manifest_small();
+        let second = decompose_manifest_small();
+
+        assert_eq!(first, second);
+        assert_eq!(first, GOLDEN_MANIFEST_SMALL.trim_end());
+    }
+
+    #[test]
+    fn decompose_components_deterministically() {
+        assert_eq!(decompose_components(), GOLDEN_COMPONENTS.trim_end());
+    }
+
+    /// The golden decompositions are written without threads, so these check
+    /// that decomposing on threads gives the same decompositions byte for byte.
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn decompose_in_parallel_deterministically() {
+        assert_eq!(decompose_manifest_small(), GOLDEN_MANIFEST_SMALL.trim_end());
+        assert_eq!(decompose_components(), GOLDEN_COMPONENTS.trim_end());
+
+        // A batch at a time, components finish merging at different steps.
+        let mut decomposition =
+            Decomposition::new(components_graph(24), &DecompositionOptions::default()).unwrap();
+        while !decomposition.step(1) {}
+        assert_eq!(
+            serde_json::to_string(&decomposition.powergraph()).unwrap(),
+            GOLDEN_COMPONENTS.trim_end()
+        );
+    }
+
+    #[test]
+    fn decompose_with_progress() {
+        let graph = || {
+            let manifest: manifest::Manifest =
+                include_str!("../../../../assets/manifest.small.json")
+                    .parse()
+                    .unwrap();
+            let (nodes, edges) = manifest.to_graph().unwrap();
+            PowerGraph::new(nodes, edges)
+        };
+
+        let mut powergraph = graph();
+        let mut recorder = progress::tests::Recorder::default();
+        powergraph
+            .decompose_with_progress(&DecompositionOptions::default(), &mut recorder)
+            .unwrap();
+
+        let phases: Vec<&str> = recorder
+            .reports
+            .iter()
+            .map(|(phase, _, _)| phase.as_str())
+            .dedup()
+            .collect();
+        assert_eq!(phases, vec!["similarity", "candidates", "power_edges"]);
+        for (_, processed, total) in recorder.reports.iter() {
+            assert!(processed <= total);
+        }
+        assert!(powergraph.power_edge_count() > 0);
+
+        // Cancelled part way through, nothing of the decomposition is kept.
+        let mut powergraph = graph();
+        let mut recorder = progress::tests::Recorder {
+            cancel_after: Some(3),
+            ..Default::default()
+        };
+        let result =
+            powergraph.decompose_with_progress(&DecompositionOptions::default(), &mut recorder);
+
+        assert!(matches!(result, Err(PowerGraphError::Cancelled)));
+        assert_eq!(recorder.reports.len(), 3);
+        assert_eq!(powergraph.power_node_count(), 0);
+        assert_eq!(powergraph.power_edge_count(), 0);
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn decompose_with_progress_in_batches() {
+        // More clusters than fit in a batch, so that progress is reported part
+        // way through the batched phases.
+        let mut powergraph = components_graph(120);
+        let mut recorder = progress::tests::Recorder::default();
+        powergraph
+            .decompose_with_progress(&DecompositionOptions::default(), &mut recorder)
+            .unwrap();
+
+        let candidates: Vec<(usize, usize)> = recorder
+            .reports
+            .iter()
+            .filter(|(phase, _, _)| phase == "candidates")
+            .map(|(_, processed, total)| (*processed, *total))
+            .collect();
+        let total = candidates.last().unwrap().1;
+        assert!(total > parallel::BATCH);
+        let batches: Vec<(usize, usize)> = (0..total)
+            .step_by(parallel::BATCH)
+            .chain([total])
+            .map(|processed| (processed, total))
+            .collect();
+        assert_eq!(candidates, batches);
+
+        // Cancellation is noticed between batches.
+        let first_batch = ("candidates".to_string(), parallel::BATCH, total);
+        let reports = recorder
+            .reports
+            .iter()
+            .position(|report| *report == first_batch)
+            .unwrap()
+            + 1;
+        let mut powergraph = components_graph(120);
+        let mut recorder = progress::tests::Recorder {
+            cancel_after: Some(reports),
+            ..Default::default()
+        };
+        let result =
+            powergraph.decompose_with_progress(&DecompositionOptions::default(), &mut recorder);
+
+        assert!(matches!(result, Err(PowerGraphError::Cancelled)));
+        assert_eq!(recorder.reports.last(), Some(&first_batch));
+        assert_eq!(powergraph.power_edge_count(), 0);
+    }
+
+    #[test]
+    fn decompose_rejects_invalid_options() {
+        let options = DecompositionOptions {
+            similarity_threshold: 1.5,
+            ..Default::default()
+        };
+        let mut powergraph = PowerGraph::new(
+            vec![
+                Node::new("a".to_string(), "{}".to_string()),
+                Node::new("b".to_string(), "{}".to_string()),
+            ],
+            vec![Edge::new("a", "b")],
+        );
+
+        assert!(matches!(
+            powergraph.decompose_with_options(&options),
+            Err(PowerGraphError::InvalidOptions(_))
+        ));
+        assert!(matches!(
+            powergraph.decompose_with_progress(&options, &mut NoProgress),
+            Err(PowerGraphError::InvalidOptions(_))
+        ));
+        assert_eq!(powergraph.power_node_count(), 0);
+    }
+
+    #[test]
+    fn comparison_sets() {
+        let powergraph = PowerGraph::new(vec![], vec![]);
+        let set = |nodes: &[NodeIndex]| nodes.iter().copied().collect::<NodeSet>();
+        let clusters = vec![
+            Cluster::new(set(&[1]), set(&[])),
+            Cluster::new(set(&[2]), set(&[1])),
+            Cluster::new(set(&[3]), set(&[1])),
+            Cluster::new(set(&[4]), set(&[2])),
+        ];
+
+        // Only clusters sharing a neighbor are compared.
+        let comparison_set = powergraph.generate_graph_comparison_set(&clusters);
+        assert_eq!(
+            comparison_set,
+            vec![UnorderedTuple {
+                one: clusters[1].clone(),
+                two: clusters[2].clone(),
+            }]
+        );
+    }
+
     #[test]
     fn powergraph_construction() {
         let nodes = (1..9)
@@ -1083,14 +1496,14 @@ mod tests {
 
     //     let powergraph = PowerGraph::new(nodes, edges);
     //     let cluster = Cluster::new(
-    //         Set::from_iter(vec!["child".to_string()]),
+    //         powergraph.interner.set(["child"]),
     //         powergraph.neighbors(&"child".to_string()),
     //     );
     //     let siblings = powergraph.find_siblings(&cluster);
 
     //     assert_eq!(
     //         siblings,
-    //         Set::from_iter(vec!["sibling".to_string(), "sibling2".to_string()])
+    //         powergraph.interner.set(["sibling", "sibling2"])
     //     );
     // }
 
@@ -1117,14 +1530,14 @@ mod tests {
 
     //     let powergraph = PowerGraph::new(nodes, edges);
     //     let cluster = Cluster::new(
-    //         Set::from_iter(vec!["child".to_string()]),
+    //         powergraph.interner.set(["child"]),
     //         powergraph.neighbors(&"child".to_string()),
     //     );
     //     let siblings = powergraph.find_siblings(&cluster);
 
     //     assert_eq!(
     //         siblings,
-    //         Set::from_iter(vec!["sibling".to_string(), "sibling2".to_string()])
+    //         powergraph.interner.set(["sibling", "sibling2"])
     //     );
     // }
 
@@ -1163,6 +1576,236 @@ mod tests {
         powergraph.decompose();
     }
 
+    #[test]
+    fn decompose_with_options() {
+        let decompose = |options: DecompositionOptions| {
+            let ids = ["a", "b", "c", "d", "e"];
+            let nodes = ids
+                .into_iter()
+                .map(|id| Node::new(id.to_string(), String::new()))
+                .collect();
+            let edges = ids
+                .into_iter()
+                .tuple_combinations()
+                .map(|(from, to)| Edge::new(from, to))
+                .collect();
+            let mut powergraph = PowerGraph::new(nodes, edges);
+            powergraph.decompose_with_options(&options).unwrap();
+
+            // Every option still covers all ten edges of the complete graph.
+            let covered: HashSet<(String, String)> = powergraph
+                .power_edges
+                .iter()
+                .filter_map(|power_edge| powergraph.expand_power_edge(power_edge))
+                .flatten()
+                .map(|edge| (edge.get_from(), edge.get_to()))
+                .filter(|(from, to)| from != to)
+                .map(|(from, to)| (from.clone().min(to.clone()), from.max(to)))
+                .collect();
+            assert_eq!(covered.len(), 10);
+
+            powergraph
+        };
+        let largest_power_node = |powergraph: &PowerGraph| {
+            powergraph
+                .power_nodes
+                .iter()
+                .map(|power_node| power_node.cluster.size())
+                .max()
+                .unwrap()
+        };
+
+        let powergraph = decompose(DecompositionOptions {
+            max_power_node_size: Some(3),
+            ..Default::default()
+        });
+        assert!(largest_power_node(&powergraph) <= 3);
+
+        let powergraph = decompose(DecompositionOptions {
+            min_power_node_size: 5,
+            ..Default::default()
+        });
+        assert!(powergraph
+            .power_nodes
+            .iter()
+            .all(|power_node| power_node.cluster.size() == 1 || power_node.cluster.size() == 5));
+
+        let powergraph = decompose(DecompositionOptions {
+            allow_cliques: false,
+            similarity_threshold: 1.0,
+            neighborhood_depth: 0,
+            scoring: CandidateScoring::Nodes,
+            ..Default::default()
+        });
+        assert!(powergraph
+            .power_edges
+            .iter()
+            .all(|power_edge| power_edge.from != power_edge.to));
+    }
+
+    #[test]
+    fn decompose_with_similarity() {
+        struct Dissimilar;
+
+        impl Similarity for Dissimilar {
+            fn coefficient(&self, _shared: usize, _one: usize, _two: usize) -> f32 {
+                0.0
+            }
+        }
+
+        let nodes = ["source", "a", "b", "c"]
+            .into_iter()
+            .map(|id| Node::new(id.to_string(), String::new()))
+            .collect();
+        let edges = vec![
+            Edge::new("source", "a"),
+            Edge::new("source", "b"),
+            Edge::new("source", "c"),
+        ];
+        let mut powergraph = PowerGraph::new(nodes, edges);
+
+        // Without any similar clusters, only singleton power nodes are found.
+        powergraph
+            .decompose_with_similarity(&DecompositionOptions::default(), &Dissimilar)
+            .unwrap();
+        assert!(powergraph
+            .power_nodes
+            .iter()
+            .all(|power_node| power_node.cluster.size() == 1));
+        assert_eq!(powergraph.power_edges.len(), 3);
+    }
+
+    #[test]
+    fn decompose_directed() {
+        let nodes = ["s1", "s2", "m1", "m2", "m3", "x", "y"]
+            .into_iter()
+            .map(|id| Node::new(id.to_string(), String::new()))
+            .collect();
+        let edges = vec![
+            Edge::new("s1", "m1"),
+            Edge::new("s1", "m2"),
+            Edge::new("s1", "m3"),
+            Edge::new("s2", "m1"),
+            Edge::new("s2", "m2"),
+            Edge::new("s2", "m3"),
+            Edge::new("m1", "x"),
+            Edge::new("m2", "x"),
+            Edge::new("m3", "x"),
+            Edge::new("m1", "y"),
+            // A node that both feeds and is fed by members of the same group.
+            Edge::new("y", "m2"),
+        ];
+        let mut powergraph = PowerGraph::new(nodes, edges.clone());
+        powergraph
+            .decompose_with_options(&DecompositionOptions {
+                directed: true,
+                ..Default::default()
+            })
+            .unwrap();
+
+        assert!(powergraph.directed);
+        let expanded: HashSet<Edge> = powergraph.expanded_edges().into_iter().collect();
+        assert_eq!(expanded, edges.into_iter().collect());
+    }
+
+    #[test]
+    fn power_node_containment() {
+        let nodes = ["a", "b", "c", "d"]
+            .into_iter()
+            .map(|id| Node::new(id.to_string(), String::new()))
+            .collect();
+        let mut powergraph = PowerGraph::new(nodes, vec![]);
+        let power_nodes = ["a-b-c-d", "a-b", "c", "a", "b", "d", "a-b-c"]
+            .into_iter()
+            .map(|id| {
+                formats::tests::power_node(&powergraph, &id.split('-').collect::<Vec<&str>>())
+            })
+            .collect();
+        powergraph.set_power_nodes(power_nodes);
+
+        powergraph.aggregate_containment();
+
+        let containment: Vec<(&str, Option<&str>, Vec<&str>, usize)> = powergraph
+            .power_nodes
+            .iter()
+            .map(|power_node| {
+                (
+                    power_node.id.as_str(),
+                    power_node.parent.as_deref(),
+                    power_node.children.iter().map(String::as_str).collect(),
+                    power_node.depth,
+                )
+            })
+            .collect();
+        assert_eq!(
+            containment,
+            vec![
+                ("a-b-c-d", None, vec!["a-b-c", "d"], 0),
+                ("a-b", Some("a-b-c"), vec!["a", "b"], 2),
+                ("c", Some("a-b-c"), vec![], 2),
+                ("a", Some("a-b"), vec![], 3),
+                ("b", Some("a-b"), vec![], 3),
+                ("d", Some("a-b-c-d"), vec![], 1),
+                ("a-b-c", Some("a-b-c-d"), vec!["a-b", "c"], 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn decompose_lossy() {
+        let nodes = ["s1", "s2", "s3", "m1", "m2", "m3"]
+            .into_iter()
+            .map(|id| Node::new(id.to_string(), String::new()))
+            .collect();
+        // Every source feeds every model, except `s3`, which does not feed `m3`.
+        let edges: Vec<Edge> = ["s1", "s2", "s3"]
+            .into_iter()
+            .cartesian_product(["m1", "m2", "m3"])
+            .filter(|pair| *pair != ("s3", "m3"))
+            .map(|(from, to)| Edge::new(from, to))
+            .collect();
+        let mut powergraph = PowerGraph::new(nodes, edges.clone());
+        powergraph
+            .decompose_with_options(&DecompositionOptions {
+                directed: true,
+                max_missing_edges: 1,
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(powergraph.power_edge_count(), 1);
+        assert_eq!(
+            powergraph.power_edges[0].negative_edges,
+            vec![Edge::new("s3", "m3")]
+        );
+        assert!(powergraph.verify().is_valid());
+        let expanded: HashSet<Edge> = powergraph.expanded_edges().into_iter().collect();
+        assert_eq!(expanded, edges.into_iter().collect());
+    }
+
+    #[test]
+    fn directed_bicliques_follow_edges() {
+        let nodes = ["a", "b", "c"]
+            .into_iter()
+            .map(|id| Node::new(id.to_string(), String::new()))
+            .collect();
+        let edges = vec![Edge::new("a", "c"), Edge::new("c", "b")];
+        let mut powergraph = PowerGraph::new(nodes, edges);
+        powergraph
+            .decompose_with_options(&DecompositionOptions {
+                directed: true,
+                ..Default::default()
+            })
+            .unwrap();
+
+        // `a` and `b` are both neighbors of `c`, but edges between them and
+        // `c` point in opposite directions.
+        assert!(powergraph
+            .power_nodes
+            .iter()
+            .all(|power_node| power_node.id != "a-b"));
+        assert_eq!(powergraph.expanded_edges().len(), 2);
+    }
+
     #[test]
     fn clusters_create_subgraph() {
         let nodes: Vec<Node> = vec![
@@ -1182,17 +1825,13 @@ mod tests {
 
         let powergraph = PowerGraph::new(nodes, edges);
         let cluster_one = Cluster::new(
-            Set::from_iter(vec!["parent".to_string()]),
-            Set::from_iter(vec![
-                "child".to_string(),
-                "sibling".to_string(),
-                "sibling2".to_string(),
-            ]),
+            powergraph.interner.set(["parent"]),
+            powergraph.interner.set(["child", "sibling", "sibling2"]),
         );
 
         let cluster_two = Cluster::new(
-            Set::from_iter(vec!["child".to_string()]),
-            Set::from_iter(vec!["parent".to_string(), "child2".to_string()]),
+            powergraph.interner.set(["child"]),
+            powergraph.interner.set(["parent", "child2"]),
         );
 
         let is_subgraph = powergraph.clusters_create_subgraph(&cluster_one, &cluster_two);
@@ -1218,17 +1857,13 @@ mod tests {
 
         let powergraph = PowerGraph::new(nodes, edges);
         let cluster_one = Cluster::new(
-            Set::from_iter(vec!["parent".to_string()]),
-            Set::from_iter(vec![
-                "child".to_string(),
-                "sibling".to_string(),
-                "sibling2".to_string(),
-            ]),
+            powergraph.interner.set(["parent"]),
+            powergraph.interner.set(["child", "sibling", "sibling2"]),
         );
 
         let cluster_two = Cluster::new(
-            Set::from_iter(vec!["child2".to_string()]),
-            Set::from_iter(vec!["child".to_string()]),
+            powergraph.interner.set(["child2"]),
+            powergraph.interner.set(["child"]),
         );
 
         let is_subgraph = powergraph.clusters_create_subgraph(&cluster_one, &cluster_two);
@@ -1253,11 +1888,11 @@ mod tests {
 
         let powergraph = PowerGraph::new(nodes, edges);
         let cluster_one = Cluster::new(
-            Set::from_iter(vec!["a".to_string(), "c".to_string()]),
-            Set::from_iter(vec!["b".to_string(), "a".to_string(), "c".to_string()]),
+            powergraph.interner.set(["a", "c"]),
+            powergraph.interner.set(["b", "a", "c"]),
         );
 
-        let is_subgraph = powergraph.clusters_are_clique(&cluster_one, &cluster_one);
+        let is_subgraph = powergraph.clusters_are_clique(&cluster_one, &cluster_one, false);
         assert!(is_subgraph);
     }
 
@@ -1279,11 +1914,11 @@ mod tests {
 
         let powergraph = PowerGraph::new(nodes, edges);
         let cluster_one = Cluster::new(
-            Set::from_iter(vec!["a".to_string()]),
-            Set::from_iter(vec!["b".to_string(), "c".to_string()]),
+            powergraph.interner.set(["a"]),
+            powergraph.interner.set(["b", "c"]),
         );
 
-        let is_subgraph = powergraph.clusters_are_clique(&cluster_one, &cluster_one);
+        let is_subgraph = powergraph.clusters_are_clique(&cluster_one, &cluster_one, false);
         assert!(is_subgraph);
     }
 
@@ -1305,11 +1940,11 @@ mod tests {
 
         let powergraph = PowerGraph::new(nodes, edges);
         let cluster_one = Cluster::new(
-            Set::from_iter(vec!["a".to_string(), "c".to_string(), "d".to_string()]),
-            Set::from_iter(vec!["b".to_string(), "a".to_string(), "c".to_string()]),
+            powergraph.interner.set(["a", "c", "d"]),
+            powergraph.interner.set(["b", "a", "c"]),
         );
 
-        let is_subgraph = powergraph.clusters_are_clique(&cluster_one, &cluster_one);
+        let is_subgraph = powergraph.clusters_are_clique(&cluster_one, &cluster_one, false);
         assert!(!is_subgraph);
     }
 
@@ -1330,16 +1965,20 @@ mod tests {
         ];
 
         let powergraph = PowerGraph::new(nodes, edges.clone());
-        let nodes = Set::from_iter(vec![
-            "a".to_string(),
-            "c".to_string(),
-            "b".to_string(),
-            "d".to_string(),
-        ]);
+        let nodes = powergraph.interner.set(["a", "c", "b", "d"]);
 
-        let subgraph_edges = powergraph.subgraph(&nodes);
+        let subgraph_edges: Vec<Edge> = powergraph
+            .subgraph(&nodes)
+            .into_iter()
+            .map(|(from, to)| {
+                Edge::new(powergraph.interner.name(from), powergraph.interner.name(to))
+            })
+            .collect();
 
-        assert!(Set::from_iter(subgraph_edges) == Set::from_iter(edges));
+        assert_eq!(
+            subgraph_edges.into_iter().collect::<HashSet<Edge>>(),
+            edges.into_iter().collect::<HashSet<Edge>>()
+        );
     }
 
     // Does the decomposition algorithm appropriately detect bicliques?
@@ -1373,4 +2012,87 @@ mod tests {
             powergraph.power_edges[0].from == "c-d-e" || powergraph.power_edges[0].to == "c-d-e"
         );
     }
+
+    /// `nodes`, which are `a` to `e`, with the power nodes `a`, `a-b` and
+    /// `c-d-e` over them, so that each aggregation is checked without a
+    /// decomposition.
+    fn aggregation_powergraph(nodes: Vec<Node>) -> PowerGraph {
+        let mut powergraph = PowerGraph::new(nodes, vec![]);
+        let power_nodes = ["a", "a-b", "c-d-e"]
+            .into_iter()
+            .map(|id| {
+                formats::tests::power_node(&powergraph, &id.split('-').collect::<Vec<&str>>())
+            })
+            .collect();
+        powergraph.set_power_nodes(power_nodes);
+        powergraph
+    }
+
+    #[test]
+    fn power_nodes_aggregate_test_counts() {
+        let mut powergraph = aggregation_powergraph(vec![
+            Node::new("a".to_string(), "{}".to_string()),
+            Node::new("b".to_string(), "{}".to_string()),
+            Node::new("c".to_string(), "{}".to_string()).with_test_count(2),
+            Node::new("d".to_string(), "{}".to_string()).with_test_count(1),
+            Node::new("e".to_string(), "{}".to_string()),
+        ]);
+
+        powergraph.aggregate_test_counts();
+
+        assert_eq!(powergraph.find_power_node("c-d-e").unwrap().test_count, 3);
+        assert_eq!(powergraph.find_power_node("a").unwrap().test_count, 0);
+    }
+
+    #[test]
+    fn power_nodes_aggregate_projects() {
+        let mut powergraph = aggregation_powergraph(vec![
+            Node::new("a".to_string(), "{}".to_string()).with_project("core".to_string()),
+            Node::new("b".to_string(), "{}".to_string()).with_project("core".to_string()),
+            Node::new("c".to_string(), "{}".to_string()).with_project("finance".to_string()),
+            Node::new("d".to_string(), "{}".to_string()).with_project("core".to_string()),
+            Node::new("e".to_string(), "{}".to_string()),
+        ]);
+
+        powergraph.aggregate_projects();
+
+        assert_eq!(
+            powergraph.find_power_node("c-d-e").unwrap().projects,
+            vec!["core", "finance"]
+        );
+        assert_eq!(
+            powergraph.find_power_node("a-b").unwrap().projects,
+            vec!["core"]
+        );
+    }
+
+    #[test]
+    fn power_nodes_share_attributes() {
+        let model = |id: &str, owner: &str| {
+            Node::new(id.to_string(), "{}".to_string()).with_attributes(
+                NodeAttributes::new()
+                    .with_resource_type("model")
+                    .with_owner(owner),
+            )
+        };
+        let mut powergraph = aggregation_powergraph(vec![
+            model("a", "core"),
+            model("b", "core"),
+            model("c", "finance"),
+            model("d", "finance"),
+            model("e", "finance"),
+        ]);
+
+        powergraph.aggregate_attributes();
+
+        let power_node = powergraph.find_power_node("c-d-e").unwrap();
+        assert_eq!(power_node.attributes().owner(), Some("finance".to_string()));
+        assert_eq!(power_node.items(), vec!["c", "d", "e"]);
+        let power_node = powergraph.find_power_node("a-b").unwrap();
+        assert_eq!(power_node.attributes().owner(), Some("core".to_string()));
+        assert_eq!(
+            power_node.attributes().resource_type(),
+            Some("model".to_string())
+        );
+    }
 }
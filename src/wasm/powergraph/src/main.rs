@@ -1,169 +1,605 @@
 use std::{
-    collections::HashMap,
-    fs::File,
-    io::{BufReader, BufWriter},
+    fmt::Display,
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
 };
 
-use powergraph::{Edge, Node, PowerGraph};
-use serde::{Deserialize, Serialize};
-use serde_json::Value;
-// use wasm_bindgen_test::console_log;
-
-#[derive(Serialize, Deserialize, Clone)]
-struct ManifestNode {
-    unique_id: String,
-    resource_type: String,
-    meta: Value,
-}
-
-#[derive(Serialize, Deserialize)]
-struct Manifest {
-    nodes: HashMap<String, ManifestNode>,
-    sources: HashMap<String, ManifestNode>,
-    child_map: HashMap<String, Vec<String>>,
-    parent_map: HashMap<String, Vec<String>>,
-}
-
-fn main() {
-    let manifest_path = std::env::args().nth(1).expect("no manifest path given");
-
-    simple_logger::SimpleLogger::new().env().init().unwrap();
-
-    let f = File::open(&manifest_path).unwrap();
-    let reader = BufReader::new(f);
-
-    let v: Manifest = serde_json::from_reader(reader).unwrap();
-
-    let mut all_nodes = v.nodes;
-    all_nodes.extend(v.sources);
-
-    let nodes: Vec<Node> = all_nodes
-        .into_iter()
-        .filter(|(_, node)| node.resource_type != "test")
-        .map(|(_, node)| {
-            Node::new(
-                node.clone().unique_id,
-                serde_json::to_string(&node).unwrap(),
-            )
-        })
-        .collect();
-    // println!("{:?}", v["nodes"]);
-
-    let mut edges: Vec<Edge> = v
-        .child_map
-        .iter()
-        .filter(|(id, _)| !id.starts_with("test"))
-        .flat_map(|(parent, children)| {
-            children
-                .into_iter()
-                .filter(|id| !id.starts_with("test"))
-                .map(|child| Edge::new(parent, child))
-                .collect::<Vec<Edge>>()
-        })
-        .collect();
-
-    let parent_map_edges: Vec<Edge> = v
-        .parent_map
-        .iter()
-        .filter(|(id, _)| !id.starts_with("test"))
-        .flat_map(|(child, parents)| {
-            parents
-                .into_iter()
-                .filter(|id| !id.starts_with("test"))
-                .map(|parent| Edge::new(parent, child))
-                .collect::<Vec<Edge>>()
-        })
-        .collect();
-
-    edges.extend(parent_map_edges);
-
-    // edges = edges
-    //     .into_iter()
-    //     .filter(|edge| !edge.get_from().starts_with("test.") && !edge.get_to().starts_with("test."))
-    //     .collect();
-
-    // let nodes: Vec<Node> = vec![
-    //     Node::new("u".to_string(), "foo".to_string()),
-    //     Node::new("v".to_string(), "foo".to_string()),
-    //     Node::new("y".to_string(), "foo".to_string()),
-    //     Node::new("w".to_string(), "bar".to_string()),
-    //     Node::new("s".to_string(), "baz".to_string()),
-    //     Node::new("x".to_string(), "fizz".to_string()),
-    //     Node::new("t".to_string(), "Boo!".to_string()),
-    //     Node::new("z".to_string(), "Boo!".to_string()),
-    // ];
-
-    // let edges: Vec<Edge> = vec![
-    //     Edge::new("v", "u"),
-    //     Edge::new("v", "w"),
-    //     Edge::new("u", "w"),
-    //     Edge::new("u", "x"),
-    //     Edge::new("u", "z"),
-    //     Edge::new("y", "s"),
-    //     Edge::new("y", "t"),
-    //     Edge::new("y", "w"),
-    //     Edge::new("w", "s"),
-    //     Edge::new("w", "t"),
-    //     Edge::new("s", "x"),
-    //     Edge::new("s", "z"),
-    //     Edge::new("x", "t"),
-    //     Edge::new("x", "z"),
-    //     Edge::new("t", "z"),
-    // ];
-
-    // let nodes: Vec<Node> = vec![
-    //     Node::new("a".to_string(), "foo".to_string()),
-    //     Node::new("b".to_string(), "foo".to_string()),
-    //     Node::new("c".to_string(), "foo".to_string()),
-    //     Node::new("d".to_string(), "bar".to_string()),
-    // ];
-
-    // let edges: Vec<Edge> = vec![
-    //     Edge::new("a", "b"),
-    //     Edge::new("a", "c"),
-    //     Edge::new("b", "c"),
-    //     Edge::new("b", "d"),
-    // ];
-
-    // let nodes: Vec<Node> = vec![
-    //     Node::new("a".to_string(), "foo".to_string()),
-    //     Node::new("b".to_string(), "foo".to_string()),
-    //     Node::new("c".to_string(), "foo".to_string()),
-    //     Node::new("d".to_string(), "bar".to_string()),
-    //     Node::new("e".to_string(), "bar".to_string()),
-    // ];
-
-    // let edges: Vec<Edge> = vec![
-    //     Edge::new("a", "b"),
-    //     Edge::new("a", "c"),
-    //     Edge::new("b", "d"),
-    //     Edge::new("c", "d"),
-    //     Edge::new("d", "e"),
-    // ];
-
-    // let nodes: Vec<Node> = vec![
-    //     Node::new("a".to_string(), "foo".to_string()),
-    //     Node::new("b".to_string(), "foo".to_string()),
-    //     Node::new("c".to_string(), "foo".to_string()),
-    //     Node::new("d".to_string(), "bar".to_string()),
-    //     Node::new("e".to_string(), "bar".to_string()),
-    // ];
-
-    // let edges: Vec<Edge> = vec![
-    //     Edge::new("a", "c"),
-    //     Edge::new("a", "d"),
-    //     Edge::new("a", "e"),
-    //     Edge::new("b", "c"),
-    //     Edge::new("b", "d"),
-    //     Edge::new("b", "e"),
-    // ];
-
-    let mut powergraph = PowerGraph::new(nodes, edges);
-    powergraph.decompose();
-
-    // Serialize it to a JSON string. and write it to a file.
-    let output_path = format!("powergraph.{}", &manifest_path);
-    let output = File::create(output_path).unwrap();
-    let mut writer = BufWriter::new(output);
-    let _ = serde_json::to_writer(writer, &powergraph);
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use log::LevelFilter;
+use powergraph::{
+    formats::{csv, cytoscape, dot, graphml, jgf},
+    manifest::{AttributeWhitelist, Manifest, ManifestOptions},
+    selector,
+    similarity::{NeighborDirection, SimilarityMeasure},
+    CandidateScoring, DecompositionOptions, Edge, Node, PowerGraph, PowerGraphError,
+    VerificationReport,
+};
+use serde::Serialize;
+
+/// Exit code for failures while writing output.
+const EXIT_OUTPUT_ERROR: u8 = 1;
+/// Exit code for invalid arguments, matching clap's usage errors.
+const EXIT_USAGE_ERROR: u8 = 2;
+/// Exit code for input files that are missing or unreadable.
+const EXIT_INPUT_ERROR: u8 = 3;
+/// Exit code for input files that cannot be parsed.
+const EXIT_MALFORMED_INPUT: u8 = 4;
+/// Exit code for inputs with edges to undefined nodes.
+const EXIT_UNKNOWN_NODE: u8 = 5;
+/// Exit code for decompositions that do not represent their input graph.
+const EXIT_MISMATCH: u8 = 6;
+
+/// Decompose dbt manifests and other graphs into power graphs.
+#[derive(Parser)]
+#[command(
+    name = "powergraph",
+    version,
+    about,
+    after_help = "Exit codes: 0 success, 1 output error, 2 usage error, 3 unreadable input, \
+                  4 malformed input, 5 unknown node reference, 6 decomposition mismatch."
+)]
+struct Cli {
+    /// Log level (off, error, warn, info, debug, trace). `RUST_LOG` takes precedence.
+    #[arg(long, global = true, default_value = "warn")]
+    log_level: LevelFilter,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Decompose a graph and write the power graph as JSON.
+    Decompose(DecomposeArgs),
+    /// Decompose a graph and report summary statistics, including edge
+    /// reduction and the time taken by each phase, as JSON.
+    Stats(DecomposeArgs),
+    /// Decompose a graph and check that the power graph represents it exactly,
+    /// writing a report of any missing, spurious or overlapping edges as JSON.
+    Verify(DecomposeArgs),
+    /// Decompose a graph and export the power graph in the given format.
+    Export(ExportArgs),
+}
+
+#[derive(Args)]
+struct InputArgs {
+    /// Path to a dbt `manifest.json` or another supported graph file. Several
+    /// manifests from the projects of a dbt Mesh may be given to merge them.
+    #[arg(required = true)]
+    inputs: Vec<PathBuf>,
+
+    /// Format of the input file. Detected from the file extension by default.
+    #[arg(long, value_enum)]
+    input_format: Option<InputFormat>,
+
+    /// Only include nodes matching a dbt-style selector, e.g. `+tag:finance`.
+    /// May be repeated; selectors are unioned.
+    #[arg(short, long)]
+    select: Vec<String>,
+
+    /// Exclude nodes matching a dbt-style selector. May be repeated.
+    #[arg(long)]
+    exclude: Vec<String>,
+
+    /// Do not ingest this resource type as graph nodes. May be repeated.
+    #[arg(long, value_enum)]
+    skip_resource: Vec<OptionalResource>,
+
+    /// Attach data tests to the nodes they test instead of discarding them.
+    #[arg(long)]
+    fold_tests: bool,
+
+    /// Keep relationship tests as test edges that are not decomposed.
+    #[arg(long)]
+    test_edges: bool,
+
+    /// Comma separated resource attributes to keep in node data, or `*` for
+    /// all. Attributes outside this list are skipped while reading.
+    #[arg(long, default_value = "meta,config")]
+    attributes: AttributeWhitelist,
+}
+
+/// Supported input file formats.
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum InputFormat {
+    /// dbt `manifest.json`.
+    Manifest,
+    /// Comma separated edge list.
+    Csv,
+    /// Tab separated edge list.
+    Tsv,
+    /// Graphviz DOT.
+    Dot,
+    /// GraphML.
+    Graphml,
+    /// JSON Graph Format.
+    Jgf,
+}
+
+impl InputFormat {
+    /// Guess the format from a file extension, falling back to a manifest.
+    fn detect(path: &Path) -> InputFormat {
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(|extension| extension.to_lowercase());
+
+        match extension.as_deref() {
+            Some("csv") => InputFormat::Csv,
+            Some("tsv") | Some("tab") => InputFormat::Tsv,
+            Some("dot") | Some("gv") => InputFormat::Dot,
+            Some("graphml") => InputFormat::Graphml,
+            Some("jgf") => InputFormat::Jgf,
+            _ => InputFormat::Manifest,
+        }
+    }
+}
+
+/// dbt resource types that can be left out of the graph.
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum OptionalResource {
+    Exposure,
+    Metric,
+    SemanticModel,
+    SavedQuery,
+    UnitTest,
+}
+
+impl InputArgs {
+    fn input_format(&self) -> InputFormat {
+        self.input_format
+            .unwrap_or_else(|| InputFormat::detect(&self.inputs[0]))
+    }
+
+    /// The first manifest-only option that was given, if any.
+    fn manifest_only_option(&self) -> Option<&'static str> {
+        if !self.select.is_empty() {
+            Some("--select")
+        } else if !self.exclude.is_empty() {
+            Some("--exclude")
+        } else if !self.skip_resource.is_empty() {
+            Some("--skip-resource")
+        } else if self.fold_tests {
+            Some("--fold-tests")
+        } else if self.test_edges {
+            Some("--test-edges")
+        } else {
+            None
+        }
+    }
+
+    fn manifest_options(&self) -> ManifestOptions {
+        let enabled = |resource: OptionalResource| !self.skip_resource.contains(&resource);
+
+        ManifestOptions {
+            exposures: enabled(OptionalResource::Exposure),
+            metrics: enabled(OptionalResource::Metric),
+            semantic_models: enabled(OptionalResource::SemanticModel),
+            saved_queries: enabled(OptionalResource::SavedQuery),
+            unit_tests: enabled(OptionalResource::UnitTest),
+            fold_tests: self.fold_tests,
+        }
+    }
+}
+
+#[derive(Args)]
+struct DecomposeArgs {
+    #[command(flatten)]
+    input: InputArgs,
+
+    #[command(flatten)]
+    options: OptionArgs,
+
+    /// Write output to this path instead of stdout. `-` also means stdout.
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct OptionArgs {
+    /// Minimum similarity for clusters to be merged, between 0 and 1.
+    #[arg(long, default_value_t = DecompositionOptions::default().similarity_threshold)]
+    similarity_threshold: f32,
+
+    /// How the similarity of two clusters is measured.
+    #[arg(long, value_enum, default_value_t = Measure::Jaccard)]
+    similarity: Measure,
+
+    /// Which neighbors of two clusters are compared for similarity.
+    #[arg(long, value_enum, default_value_t = Direction::Any)]
+    similarity_neighbors: Direction,
+
+    /// Number of rounds of neighborhood expansion.
+    #[arg(long, default_value_t = DecompositionOptions::default().neighborhood_depth)]
+    neighborhood_depth: usize,
+
+    /// Minimum number of members of a power node with more than one member.
+    #[arg(long, default_value_t = DecompositionOptions::default().min_power_node_size)]
+    min_power_node_size: usize,
+
+    /// Maximum number of members of a power node.
+    #[arg(long)]
+    max_power_node_size: Option<usize>,
+
+    /// Do not create power edges from a power node to itself.
+    #[arg(long)]
+    no_cliques: bool,
+
+    /// Respect the direction of edges, so that every power edge means each
+    /// node of its source feeds each node of its target.
+    #[arg(long)]
+    directed: bool,
+
+    /// Allow power edges to be missing up to this many edges, which are kept
+    /// as negative edges.
+    #[arg(long, default_value_t = 0)]
+    max_missing_edges: usize,
+
+    /// Allow power edges to be missing up to this fraction of their edges,
+    /// which are kept as negative edges.
+    #[arg(long, default_value_t = 0.0)]
+    max_missing_ratio: f32,
+
+    /// How power edge candidates are ranked.
+    #[arg(long, value_enum, default_value_t = Scoring::Edges)]
+    scoring: Scoring,
+}
+
+/// Measures of the similarity of two clusters' neighbors.
+#[derive(Clone, Copy, ValueEnum)]
+enum Measure {
+    /// Shared neighbors over all neighbors.
+    Jaccard,
+    /// Twice the shared neighbors over the sum of both sets of neighbors.
+    Dice,
+    /// Shared neighbors over the smaller set of neighbors.
+    Overlap,
+    /// Cosine similarity of the neighbors as binary vectors.
+    Cosine,
+}
+
+/// Neighbors compared for similarity.
+#[derive(Clone, Copy, ValueEnum)]
+enum Direction {
+    /// Parents and children.
+    Any,
+    /// Upstream nodes only.
+    Parents,
+    /// Downstream nodes only.
+    Children,
+}
+
+/// Ways of ranking power edge candidates.
+#[derive(Clone, Copy, ValueEnum)]
+enum Scoring {
+    /// By the number of edges covered.
+    Edges,
+    /// By the number of nodes covered.
+    Nodes,
+}
+
+impl OptionArgs {
+    fn decomposition_options(&self) -> Result<DecompositionOptions, CliError> {
+        let options = DecompositionOptions {
+            similarity_threshold: self.similarity_threshold,
+            similarity: match self.similarity {
+                Measure::Jaccard => SimilarityMeasure::Jaccard,
+                Measure::Dice => SimilarityMeasure::SorensenDice,
+                Measure::Overlap => SimilarityMeasure::Overlap,
+                Measure::Cosine => SimilarityMeasure::Cosine,
+            },
+            neighbor_direction: match self.similarity_neighbors {
+                Direction::Any => NeighborDirection::Any,
+                Direction::Parents => NeighborDirection::Parents,
+                Direction::Children => NeighborDirection::Children,
+            },
+            neighborhood_depth: self.neighborhood_depth,
+            min_power_node_size: self.min_power_node_size,
+            max_power_node_size: self.max_power_node_size,
+            allow_cliques: !self.no_cliques,
+            directed: self.directed,
+            max_missing_edges: self.max_missing_edges,
+            max_missing_ratio: self.max_missing_ratio,
+            scoring: match self.scoring {
+                Scoring::Edges => CandidateScoring::Edges,
+                Scoring::Nodes => CandidateScoring::Nodes,
+            },
+        };
+        options
+            .validate()
+            .map_err(|error| CliError::Usage(error.to_string()))?;
+        Ok(options)
+    }
+}
+
+#[derive(Args)]
+struct ExportArgs {
+    #[command(flatten)]
+    decompose: DecomposeArgs,
+
+    /// Output format.
+    #[arg(short, long, value_enum, default_value_t = ExportFormat::Json)]
+    format: ExportFormat,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ExportFormat {
+    Json,
+    /// Graphviz DOT, with power nodes as nested clusters.
+    Dot,
+    /// GraphML, with power nodes as nested graphs.
+    Graphml,
+    /// Cytoscape.js elements, with power nodes as compound nodes.
+    Cytoscape,
+}
+
+#[derive(Debug)]
+enum CliError {
+    Input {
+        path: PathBuf,
+        source: PowerGraphError,
+    },
+    Output {
+        path: Option<PathBuf>,
+        source: io::Error,
+    },
+    Selector(PowerGraphError),
+    /// Errors from manifests merged into a dbt Mesh, which cannot be
+    /// attributed to a single input file.
+    Mesh(PowerGraphError),
+    Usage(String),
+    /// A decomposition that does not represent its input graph.
+    Mismatch(VerificationReport),
+}
+
+/// The exit code for an error raised while loading the input.
+fn input_exit_code(error: &PowerGraphError) -> u8 {
+    match error {
+        PowerGraphError::Io(_) => EXIT_INPUT_ERROR,
+        PowerGraphError::Parse(_)
+        | PowerGraphError::Syntax { .. }
+        | PowerGraphError::InvalidMesh(_) => EXIT_MALFORMED_INPUT,
+        PowerGraphError::UnknownNode { .. } => EXIT_UNKNOWN_NODE,
+        PowerGraphError::InvalidSelector(_) | PowerGraphError::InvalidOptions(_) => {
+            EXIT_USAGE_ERROR
+        }
+        PowerGraphError::Cancelled => unreachable!("the CLI never cancels a decomposition"),
+    }
+}
+
+impl CliError {
+    fn exit_code(&self) -> u8 {
+        match self {
+            CliError::Input { source, .. } | CliError::Mesh(source) => input_exit_code(source),
+            CliError::Output { .. } => EXIT_OUTPUT_ERROR,
+            CliError::Selector(_) | CliError::Usage(_) => EXIT_USAGE_ERROR,
+            CliError::Mismatch(_) => EXIT_MISMATCH,
+        }
+    }
+}
+
+impl Display for CliError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CliError::Input { path, source } => write!(f, "{}: {}", path.display(), source),
+            CliError::Output { path, source } => match path {
+                Some(path) => write!(f, "could not write {}: {}", path.display(), source),
+                None => write!(f, "could not write to stdout: {}", source),
+            },
+            CliError::Selector(source) | CliError::Mesh(source) => write!(f, "{}", source),
+            CliError::Usage(message) => write!(f, "{}", message),
+            CliError::Mismatch(report) => write!(
+                f,
+                "the decomposition does not match the input: {} missing, {} spurious and {} overlapping edges, {} crossing power nodes",
+                report.missing_edges.len(),
+                report.spurious_edges.len(),
+                report
+                    .overlapping_power_edges
+                    .iter()
+                    .map(|overlap| overlap.edges.len())
+                    .sum::<usize>(),
+                report.crossing_power_nodes.len()
+            ),
+        }
+    }
+}
+
+/// The graph loaded from the input files, ready for decomposition.
+struct Graph {
+    nodes: Vec<Node>,
+    edges: Vec<Edge>,
+    test_edges: Vec<Edge>,
+}
+
+fn input_error(path: &Path) -> impl Fn(PowerGraphError) -> CliError + '_ {
+    move |source| CliError::Input {
+        path: path.to_path_buf(),
+        source,
+    }
+}
+
+fn load_manifest(path: &Path, attributes: &AttributeWhitelist) -> Result<Manifest, CliError> {
+    let file = File::open(path).map_err(|error| input_error(path)(error.into()))?;
+    Manifest::from_reader_with_attributes(BufReader::new(file), attributes)
+        .map_err(input_error(path))
+}
+
+fn load_graph(args: &InputArgs) -> Result<Graph, CliError> {
+    let format = args.input_format();
+    if format != InputFormat::Manifest {
+        if let Some(option) = args.manifest_only_option() {
+            return Err(CliError::Usage(format!(
+                "{} can only be used with dbt manifests",
+                option
+            )));
+        }
+        if args.inputs.len() > 1 {
+            return Err(CliError::Usage(
+                "only dbt manifests can be merged".to_string(),
+            ));
+        }
+
+        let path = &args.inputs[0];
+        let input_error = input_error(path);
+
+        let input = fs::read_to_string(path).map_err(|error| input_error(error.into()))?;
+        let (nodes, edges) = match format {
+            InputFormat::Csv => csv::read_csv(&input),
+            InputFormat::Tsv => csv::read_tsv(&input),
+            InputFormat::Dot => dot::read_dot(&input),
+            InputFormat::Graphml => graphml::read_graphml(&input),
+            InputFormat::Jgf => jgf::read_jgf(&input),
+            InputFormat::Manifest => unreachable!(),
+        }
+        .map_err(input_error)?;
+
+        return Ok(Graph {
+            nodes,
+            edges,
+            test_edges: Vec::new(),
+        });
+    }
+
+    let manifest = match args.inputs.as_slice() {
+        [path] => load_manifest(path, &args.attributes)?,
+        paths => {
+            let manifests = paths
+                .iter()
+                .map(|path| load_manifest(path, &args.attributes))
+                .collect::<Result<Vec<Manifest>, CliError>>()?;
+            Manifest::merge(manifests).map_err(CliError::Mesh)?
+        }
+    };
+    let graph_error = |source| match args.inputs.as_slice() {
+        [path] => input_error(path)(source),
+        _ => CliError::Mesh(source),
+    };
+
+    let options = args.manifest_options();
+
+    let selected = if args.select.is_empty() && args.exclude.is_empty() {
+        None
+    } else {
+        let select = Some(args.select.join(" ")).filter(|select| !select.is_empty());
+        let exclude = Some(args.exclude.join(" ")).filter(|exclude| !exclude.is_empty());
+        Some(
+            selector::select_nodes(&manifest, select.as_deref(), exclude.as_deref())
+                .map_err(CliError::Selector)?,
+        )
+    };
+
+    let (nodes, edges) = manifest
+        .to_graph_with_options(&options, selected.as_ref())
+        .map_err(graph_error)?;
+
+    let test_edges = if args.test_edges {
+        manifest.relationship_test_edges(&nodes)
+    } else {
+        Vec::new()
+    };
+
+    Ok(Graph {
+        nodes,
+        edges,
+        test_edges,
+    })
+}
+
+fn decompose(args: &DecomposeArgs) -> Result<PowerGraph, CliError> {
+    let options = args.options.decomposition_options()?;
+    let graph = load_graph(&args.input)?;
+
+    let mut powergraph = PowerGraph::new(graph.nodes, graph.edges);
+    powergraph.set_test_edges(graph.test_edges);
+    powergraph
+        .decompose_with_options(&options)
+        .map_err(|error| CliError::Usage(error.to_string()))?;
+
+    Ok(powergraph)
+}
+
+/// Write to `path`, or to stdout if no path (or `-`) is given.
+fn write_output(
+    path: &Option<PathBuf>,
+    write: impl FnOnce(&mut dyn Write) -> io::Result<()>,
+) -> Result<(), CliError> {
+    let path = path.as_ref().filter(|path| path.as_os_str() != "-");
+    let output_error = |source: io::Error| CliError::Output {
+        path: path.cloned(),
+        source,
+    };
+
+    let mut writer: Box<dyn Write> = match path {
+        Some(path) => Box::new(BufWriter::new(File::create(path).map_err(output_error)?)),
+        None => Box::new(BufWriter::new(io::stdout().lock())),
+    };
+
+    write(&mut writer).map_err(output_error)?;
+    writer.flush().map_err(output_error)
+}
+
+/// Write `value` as JSON to `path`, or to stdout if no path (or `-`) is given.
+fn write_json<T: Serialize>(path: &Option<PathBuf>, value: &T) -> Result<(), CliError> {
+    write_output(path, |writer| {
+        serde_json::to_writer(&mut *writer, value)?;
+        writeln!(writer)
+    })
+}
+
+fn run(cli: Cli) -> Result<(), CliError> {
+    match cli.command {
+        Command::Decompose(args) => {
+            let powergraph = decompose(&args)?;
+            write_json(&args.output, &powergraph)
+        }
+        Command::Stats(args) => {
+            let powergraph = decompose(&args)?;
+            write_json(&args.output, &powergraph.stats())
+        }
+        Command::Verify(args) => {
+            let powergraph = decompose(&args)?;
+            let report = powergraph.verify();
+            write_json(&args.output, &report)?;
+            if report.is_valid() {
+                Ok(())
+            } else {
+                Err(CliError::Mismatch(report))
+            }
+        }
+        Command::Export(args) => {
+            let powergraph = decompose(&args.decompose)?;
+            match args.format {
+                ExportFormat::Json => write_json(&args.decompose.output, &powergraph),
+                ExportFormat::Dot => write_output(&args.decompose.output, |writer| {
+                    writer.write_all(powergraph.to_dot().as_bytes())
+                }),
+                ExportFormat::Graphml => write_output(&args.decompose.output, |writer| {
+                    writer.write_all(graphml::write_graphml(&powergraph).as_bytes())
+                }),
+                ExportFormat::Cytoscape => write_json(
+                    &args.decompose.output,
+                    &cytoscape::write_cytoscape(&powergraph),
+                ),
+            }
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    let _ = simple_logger::SimpleLogger::new()
+        .with_level(cli.log_level)
+        .env()
+        .init();
+
+    match run(cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("error: {}", error);
+            ExitCode::from(error.exit_code())
+        }
+    }
 }
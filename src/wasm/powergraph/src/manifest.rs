@@ -0,0 +1,1191 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::io::Read;
+use std::str::FromStr;
+
+use itertools::Itertools;
+use serde::de::{self, DeserializeSeed, Deserializer, IgnoredAny, MapAccess, Visitor};
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
+
+use crate::{error::PowerGraphError, Edge, Node, NodeAttributes};
+
+/// A manifest resource. Only the fields needed to build and select from the
+/// graph are parsed; any other attributes are kept verbatim, as raw JSON, if
+/// they are in the `AttributeWhitelist` the manifest was read with.
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct ManifestNode {
+    pub unique_id: String,
+    pub resource_type: String,
+    pub name: String,
+    pub package_name: String,
+    pub original_file_path: String,
+    pub fqn: Vec<String>,
+    pub tags: Vec<String>,
+    pub depends_on: DependsOn,
+    /// Only written to `Node.data` if `config` is in the attribute whitelist,
+    /// in which case the full config is kept in `attributes`.
+    #[serde(skip_serializing)]
+    pub config: NodeConfig,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub test_metadata: Option<TestMetadata>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attached_node: Option<String>,
+    /// The dbt project this resource was loaded from, when several manifests
+    /// are merged with `Manifest::merge`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    /// The scalar values of `meta`, as strings. Only written to `Node.data` if
+    /// `meta` is in the attribute whitelist, in which case the full meta is
+    /// kept in `attributes`.
+    #[serde(skip)]
+    pub meta: BTreeMap<String, String>,
+    /// Whitelisted attributes, as raw JSON.
+    #[serde(skip)]
+    pub attributes: BTreeMap<String, Box<RawValue>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct DependsOn {
+    #[serde(default)]
+    pub nodes: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct NodeConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub severity: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub materialized: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+}
+
+/// Metadata describing which generic test a test node is an instance of.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TestMetadata {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+}
+
+/// A data test folded into the node(s) it tests.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct FoldedTest {
+    pub name: String,
+    /// The generic test name (e.g. `unique`), or `singular`.
+    pub test_type: String,
+    pub column: Option<String>,
+    pub severity: Option<String>,
+}
+
+impl ManifestNode {
+    /// The typed attributes of this resource. The owner is taken from
+    /// `meta.owner`, falling back to the resource's group, and the other
+    /// scalar `meta` values are kept as key/value pairs.
+    pub fn node_attributes(&self) -> NodeAttributes {
+        let mut attributes = NodeAttributes::new()
+            .with_resource_type(self.resource_type.clone())
+            .with_fqn(self.fqn.clone())
+            .with_tags(self.tags.clone());
+
+        if !self.package_name.is_empty() {
+            attributes = attributes.with_package(self.package_name.clone());
+        }
+
+        if let Some(materialized) = &self.config.materialized {
+            attributes = attributes.with_materialization(materialized.clone());
+        }
+
+        let owner = self
+            .meta
+            .get("owner")
+            .or(self.group.as_ref())
+            .or(self.config.group.as_ref());
+        if let Some(owner) = owner {
+            attributes = attributes.with_owner(owner.clone());
+        }
+
+        for (key, value) in self.meta.iter().filter(|(key, _)| *key != "owner") {
+            attributes = attributes.with(key.clone(), value.clone());
+        }
+
+        attributes
+    }
+}
+
+/// The scalar values of a `meta` object, as strings.
+fn meta_values(meta: Option<BTreeMap<String, serde_json::Value>>) -> BTreeMap<String, String> {
+    meta.unwrap_or_default()
+        .into_iter()
+        .filter_map(|(key, value)| match value {
+            serde_json::Value::String(value) => Some((key, value)),
+            serde_json::Value::Number(value) => Some((key, value.to_string())),
+            serde_json::Value::Bool(value) => Some((key, value.to_string())),
+            _ => None,
+        })
+        .collect()
+}
+
+impl FoldedTest {
+    fn from_test_node(node: &ManifestNode) -> FoldedTest {
+        FoldedTest {
+            name: node.name.clone(),
+            test_type: node
+                .test_metadata
+                .as_ref()
+                .map(|metadata| metadata.name.clone())
+                .unwrap_or_else(|| "singular".to_string()),
+            column: node.column_name.clone(),
+            severity: node.config.severity.clone(),
+        }
+    }
+}
+
+/// The serialized `Node.data` for a manifest resource.
+#[derive(Serialize)]
+struct NodeData<'a> {
+    #[serde(flatten)]
+    node: &'a ManifestNode,
+    #[serde(flatten)]
+    attributes: &'a BTreeMap<String, Box<RawValue>>,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    tests: &'a [FoldedTest],
+}
+
+/// The subset of the manifest `metadata` block used to identify a project.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ManifestMetadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project_name: Option<String>,
+}
+
+/// The subset of a dbt `manifest.json` required to build a graph.
+#[derive(Debug, Default)]
+pub struct Manifest {
+    pub metadata: ManifestMetadata,
+    pub nodes: BTreeMap<String, ManifestNode>,
+    pub sources: BTreeMap<String, ManifestNode>,
+    pub exposures: BTreeMap<String, ManifestNode>,
+    pub metrics: BTreeMap<String, ManifestNode>,
+    pub semantic_models: BTreeMap<String, ManifestNode>,
+    pub saved_queries: BTreeMap<String, ManifestNode>,
+    pub unit_tests: BTreeMap<String, ManifestNode>,
+    pub child_map: BTreeMap<String, Vec<String>>,
+    pub parent_map: BTreeMap<String, Vec<String>>,
+}
+
+/// The resource attributes, beyond those needed to build the graph, that are
+/// kept when reading a manifest and written to `Node.data`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AttributeWhitelist {
+    /// Keep every attribute. This can use a lot of memory on large manifests,
+    /// as it includes compiled SQL and column documentation.
+    All,
+    /// Keep only the named attributes.
+    Only(HashSet<String>),
+}
+
+impl AttributeWhitelist {
+    fn contains(&self, attribute: &str) -> bool {
+        match self {
+            AttributeWhitelist::All => true,
+            AttributeWhitelist::Only(attributes) => attributes.contains(attribute),
+        }
+    }
+}
+
+impl Default for AttributeWhitelist {
+    /// `meta` and `config`, which carry ownership information.
+    fn default() -> Self {
+        AttributeWhitelist::Only(HashSet::from_iter([
+            "meta".to_string(),
+            "config".to_string(),
+        ]))
+    }
+}
+
+impl FromStr for AttributeWhitelist {
+    type Err = PowerGraphError;
+
+    /// Parse a comma separated list of attribute names, or `*` for all.
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if value.trim() == "*" {
+            return Ok(AttributeWhitelist::All);
+        }
+
+        Ok(AttributeWhitelist::Only(
+            value
+                .split(',')
+                .map(str::trim)
+                .filter(|attribute| !attribute.is_empty())
+                .map(str::to_string)
+                .collect(),
+        ))
+    }
+}
+
+/// Toggles for the optional dbt resource types ingested as graph nodes. Models,
+/// seeds, snapshots and sources are always ingested.
+#[derive(Debug, Clone)]
+pub struct ManifestOptions {
+    pub exposures: bool,
+    pub metrics: bool,
+    pub semantic_models: bool,
+    pub saved_queries: bool,
+    pub unit_tests: bool,
+    /// Attach data tests to the nodes they test instead of discarding them.
+    pub fold_tests: bool,
+}
+
+impl Default for ManifestOptions {
+    fn default() -> Self {
+        ManifestOptions {
+            exposures: true,
+            metrics: true,
+            semantic_models: true,
+            saved_queries: true,
+            unit_tests: true,
+            fold_tests: false,
+        }
+    }
+}
+
+impl Manifest {
+    /// Read a manifest, keeping the default `AttributeWhitelist`.
+    pub fn from_reader<R: Read>(reader: R) -> Result<Manifest, PowerGraphError> {
+        Manifest::from_reader_with_attributes(reader, &AttributeWhitelist::default())
+    }
+
+    /// Read a manifest in a single streaming pass, keeping only the resource
+    /// attributes in `attributes`. Everything else, including whole sections
+    /// such as `macros` and `docs`, is skipped without being buffered.
+    pub fn from_reader_with_attributes<R: Read>(
+        reader: R,
+        attributes: &AttributeWhitelist,
+    ) -> Result<Manifest, PowerGraphError> {
+        let mut deserializer = serde_json::Deserializer::from_reader(reader);
+        let manifest = ManifestSeed(attributes).deserialize(&mut deserializer)?;
+        deserializer.end()?;
+        Ok(manifest)
+    }
+
+    /// Parse a manifest from a string, keeping only the resource attributes in
+    /// `attributes`.
+    pub fn from_str_with_attributes(
+        value: &str,
+        attributes: &AttributeWhitelist,
+    ) -> Result<Manifest, PowerGraphError> {
+        let mut deserializer = serde_json::Deserializer::from_str(value);
+        let manifest = ManifestSeed(attributes).deserialize(&mut deserializer)?;
+        deserializer.end()?;
+        Ok(manifest)
+    }
+
+    /// Merge the manifests of several dbt projects, such as the projects of a
+    /// dbt Mesh, into one manifest. Every id is namespaced as
+    /// `<project>:<unique_id>` using each manifest's `metadata.project_name`,
+    /// and every resource is tagged with its project.
+    ///
+    /// A resource defined in one project and referenced from another, as with
+    /// cross-project `ref`s to public models, is resolved to the project that
+    /// owns it (the project matching its `package_name`), so references become
+    /// edges between the projects' nodes. The copy of the resource in the
+    /// referencing project's manifest is dropped in favour of the original.
+    pub fn merge(manifests: Vec<Manifest>) -> Result<Manifest, PowerGraphError> {
+        let mut projects: Vec<String> = Vec::new();
+        for (index, manifest) in manifests.iter().enumerate() {
+            let project = manifest.metadata.project_name.clone().ok_or_else(|| {
+                PowerGraphError::InvalidMesh(format!(
+                    "manifest {} has no `metadata.project_name`",
+                    index + 1
+                ))
+            })?;
+            if projects.contains(&project) {
+                return Err(PowerGraphError::InvalidMesh(format!(
+                    "project `{}` is loaded more than once",
+                    project
+                )));
+            }
+            projects.push(project);
+        }
+
+        // The project that defines each resource of its own package.
+        let mut owners: HashMap<String, String> = HashMap::new();
+        for (manifest, project) in manifests.iter().zip(projects.iter()) {
+            for resource in manifest.resources() {
+                if &resource.package_name == project {
+                    owners.insert(resource.unique_id.clone(), project.clone());
+                }
+            }
+        }
+
+        let namespace = |project: &str, id: &str| match owners.get(id) {
+            Some(owner) => format!("{}:{}", owner, id),
+            None => format!("{}:{}", project, id),
+        };
+        let is_foreign = |project: &str, id: &str| {
+            owners
+                .get(id)
+                .is_some_and(|owner| owner.as_str() != project)
+        };
+
+        let mut merged = Manifest::default();
+
+        for (manifest, project) in manifests.into_iter().zip(projects.iter()) {
+            let merge_resources =
+                |target: &mut BTreeMap<String, ManifestNode>,
+                 resources: BTreeMap<String, ManifestNode>| {
+                    for (id, mut resource) in resources {
+                        if is_foreign(project, &id) {
+                            continue;
+                        }
+
+                        resource.unique_id = namespace(project, &resource.unique_id);
+                        for parent in resource.depends_on.nodes.iter_mut() {
+                            *parent = namespace(project, parent);
+                        }
+                        if let Some(attached_node) = resource.attached_node.as_mut() {
+                            *attached_node = namespace(project, attached_node);
+                        }
+                        resource.project = Some(project.clone());
+
+                        target.insert(namespace(project, &id), resource);
+                    }
+                };
+
+            merge_resources(&mut merged.nodes, manifest.nodes);
+            merge_resources(&mut merged.sources, manifest.sources);
+            merge_resources(&mut merged.exposures, manifest.exposures);
+            merge_resources(&mut merged.metrics, manifest.metrics);
+            merge_resources(&mut merged.semantic_models, manifest.semantic_models);
+            merge_resources(&mut merged.saved_queries, manifest.saved_queries);
+            merge_resources(&mut merged.unit_tests, manifest.unit_tests);
+
+            let merge_map = |target: &mut BTreeMap<String, Vec<String>>,
+                             map: BTreeMap<String, Vec<String>>| {
+                for (id, references) in map {
+                    let entry = target.entry(namespace(project, &id)).or_default();
+                    for reference in references {
+                        let reference = namespace(project, &reference);
+                        if !entry.contains(&reference) {
+                            entry.push(reference);
+                        }
+                    }
+                }
+            };
+
+            merge_map(&mut merged.child_map, manifest.child_map);
+            merge_map(&mut merged.parent_map, manifest.parent_map);
+        }
+
+        Ok(merged)
+    }
+
+    /// Every resource in the manifest that may become a node in the graph.
+    pub fn resources(&self) -> impl Iterator<Item = &ManifestNode> {
+        self.nodes
+            .values()
+            .chain(self.sources.values())
+            .chain(self.exposures.values())
+            .chain(self.metrics.values())
+            .chain(self.semantic_models.values())
+            .chain(self.saved_queries.values())
+            .chain(self.unit_tests.values())
+    }
+
+    fn ingested_resources<'a>(
+        &'a self,
+        options: &ManifestOptions,
+    ) -> impl Iterator<Item = &'a ManifestNode> {
+        let optional = [
+            (options.exposures, &self.exposures),
+            (options.metrics, &self.metrics),
+            (options.semantic_models, &self.semantic_models),
+            (options.saved_queries, &self.saved_queries),
+            (options.unit_tests, &self.unit_tests),
+        ];
+
+        self.nodes.values().chain(self.sources.values()).chain(
+            optional
+                .into_iter()
+                .filter(|(enabled, _)| *enabled)
+                .flat_map(|(_, resources)| resources.values()),
+        )
+    }
+
+    fn tests(&self) -> impl Iterator<Item = &ManifestNode> {
+        self.nodes
+            .values()
+            .filter(|node| node.resource_type == "test")
+    }
+
+    /// Group the data tests in the manifest by the unique_id of each node they test.
+    pub fn folded_tests(&self) -> HashMap<&str, Vec<FoldedTest>> {
+        let mut folded_tests: HashMap<&str, Vec<FoldedTest>> = HashMap::new();
+
+        for test in self.tests() {
+            for tested in test.depends_on.nodes.iter().unique() {
+                folded_tests
+                    .entry(tested.as_str())
+                    .or_default()
+                    .push(FoldedTest::from_test_node(test));
+            }
+        }
+
+        folded_tests
+    }
+
+    /// Edges derived from `relationships` tests, from the node under test to the
+    /// node it references. Only edges between the given `nodes` are returned.
+    pub fn relationship_test_edges(&self, nodes: &[Node]) -> Vec<Edge> {
+        let node_ids: HashSet<&str> = nodes.iter().map(|node| node.id.as_str()).collect();
+
+        self.tests()
+            .filter(|test| {
+                test.test_metadata
+                    .as_ref()
+                    .is_some_and(|metadata| metadata.name == "relationships")
+            })
+            .filter_map(|test| {
+                let from = test
+                    .attached_node
+                    .as_ref()
+                    .or_else(|| test.depends_on.nodes.first())?;
+                let to = test.depends_on.nodes.iter().find(|id| *id != from)?;
+                Some(Edge::new(from, to))
+            })
+            .filter(|edge| {
+                node_ids.contains(edge.from.as_str()) && node_ids.contains(edge.to.as_str())
+            })
+            .unique()
+            .collect()
+    }
+
+    /// Check that every id referenced by the `child_map`, `parent_map` and
+    /// `depends_on` of each resource is defined somewhere in the manifest.
+    pub fn validate(&self) -> Result<(), PowerGraphError> {
+        let known: HashSet<&String> = self
+            .resources()
+            .map(|resource| &resource.unique_id)
+            .chain(self.child_map.keys())
+            .chain(self.parent_map.keys())
+            .collect();
+
+        let depends_on = self
+            .resources()
+            .map(|resource| (&resource.unique_id, &resource.depends_on.nodes));
+
+        for (id, references) in self
+            .child_map
+            .iter()
+            .chain(self.parent_map.iter())
+            .chain(depends_on)
+        {
+            if let Some(unknown) = references
+                .iter()
+                .find(|reference| !known.contains(reference))
+            {
+                return Err(PowerGraphError::UnknownNode {
+                    id: unknown.clone(),
+                    referenced_by: id.clone(),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Convert the manifest into the nodes and edges of a graph. Tests are
+    /// excluded, as are edges to resources that are not part of the graph.
+    pub fn to_graph(&self) -> Result<(Vec<Node>, Vec<Edge>), PowerGraphError> {
+        self.to_graph_with_options(&ManifestOptions::default(), None)
+    }
+
+    /// Convert the manifest into a graph containing only the `selected` nodes,
+    /// such as those returned by `selector::select_nodes`.
+    pub fn to_selected_graph(
+        &self,
+        selected: &HashSet<String>,
+    ) -> Result<(Vec<Node>, Vec<Edge>), PowerGraphError> {
+        self.to_graph_with_options(&ManifestOptions::default(), Some(selected))
+    }
+
+    /// Convert the manifest into a graph, ingesting the resource types enabled
+    /// in `options` and, if given, only the `selected` nodes.
+    pub fn to_graph_with_options(
+        &self,
+        options: &ManifestOptions,
+        selected: Option<&HashSet<String>>,
+    ) -> Result<(Vec<Node>, Vec<Edge>), PowerGraphError> {
+        self.validate()?;
+
+        let folded_tests = if options.fold_tests {
+            self.folded_tests()
+        } else {
+            HashMap::new()
+        };
+
+        let nodes: Vec<Node> = self
+            .ingested_resources(options)
+            .filter(|node| node.resource_type != "test")
+            .filter(|node| selected.is_none_or(|selected| selected.contains(&node.unique_id)))
+            .map(|node| {
+                let tests = folded_tests
+                    .get(node.unique_id.as_str())
+                    .map(Vec::as_slice)
+                    .unwrap_or_default();
+                let data = serde_json::to_string(&NodeData {
+                    node,
+                    attributes: &node.attributes,
+                    tests,
+                })?;
+
+                let graph_node = Node::new(node.unique_id.clone(), data)
+                    .with_test_count(tests.len())
+                    .with_attributes(node.node_attributes());
+                Ok(match &node.project {
+                    Some(project) => graph_node.with_project(project.clone()),
+                    None => graph_node,
+                })
+            })
+            .collect::<Result<Vec<Node>, PowerGraphError>>()?;
+
+        let node_ids: HashSet<&str> = nodes.iter().map(|node| node.id.as_str()).collect();
+
+        let child_map_edges = self.child_map.iter().flat_map(|(parent, children)| {
+            children.iter().map(move |child| Edge::new(parent, child))
+        });
+        let parent_map_edges = self.parent_map.iter().flat_map(|(child, parents)| {
+            parents.iter().map(move |parent| Edge::new(parent, child))
+        });
+        let depends_on_edges = self.resources().flat_map(|resource| {
+            resource
+                .depends_on
+                .nodes
+                .iter()
+                .map(move |parent| Edge::new(parent, &resource.unique_id))
+        });
+
+        let edges: Vec<Edge> = child_map_edges
+            .chain(parent_map_edges)
+            .chain(depends_on_edges)
+            .filter(|edge| {
+                node_ids.contains(edge.from.as_str()) && node_ids.contains(edge.to.as_str())
+            })
+            .unique()
+            .collect();
+
+        Ok((nodes, edges))
+    }
+}
+
+impl FromStr for Manifest {
+    type Err = PowerGraphError;
+
+    fn from_str(value: &str) -> Result<Manifest, PowerGraphError> {
+        Manifest::from_str_with_attributes(value, &AttributeWhitelist::default())
+    }
+}
+
+/// Deserializes a whole manifest, skipping the sections that are not needed.
+struct ManifestSeed<'a>(&'a AttributeWhitelist);
+
+impl<'de> DeserializeSeed<'de> for ManifestSeed<'_> {
+    type Value = Manifest;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Manifest, D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de> Visitor<'de> for ManifestSeed<'_> {
+    type Value = Manifest;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a dbt manifest")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Manifest, A::Error> {
+        let mut manifest = Manifest::default();
+
+        while let Some(key) = map.next_key::<String>()? {
+            let resources = match key.as_str() {
+                "metadata" => {
+                    manifest.metadata = map.next_value()?;
+                    continue;
+                }
+                "child_map" => {
+                    manifest.child_map = map.next_value()?;
+                    continue;
+                }
+                "parent_map" => {
+                    manifest.parent_map = map.next_value()?;
+                    continue;
+                }
+                "nodes" => &mut manifest.nodes,
+                "sources" => &mut manifest.sources,
+                "exposures" => &mut manifest.exposures,
+                "metrics" => &mut manifest.metrics,
+                "semantic_models" => &mut manifest.semantic_models,
+                "saved_queries" => &mut manifest.saved_queries,
+                "unit_tests" => &mut manifest.unit_tests,
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                    continue;
+                }
+            };
+            *resources = map.next_value_seed(ResourcesSeed(self.0))?;
+        }
+
+        Ok(manifest)
+    }
+}
+
+/// Deserializes one section of resources keyed by unique_id.
+struct ResourcesSeed<'a>(&'a AttributeWhitelist);
+
+impl<'de> DeserializeSeed<'de> for ResourcesSeed<'_> {
+    type Value = BTreeMap<String, ManifestNode>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for ResourcesSeed<'_> {
+    type Value = BTreeMap<String, ManifestNode>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a map of dbt resources")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut resources = BTreeMap::new();
+
+        while let Some(id) = map.next_key::<String>()? {
+            let resource = map.next_value_seed(ResourceSeed(self.0))?;
+            resources.insert(id, resource);
+        }
+
+        Ok(resources)
+    }
+
+    /// dbt writes `null` for sections with no resources.
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(BTreeMap::new())
+    }
+}
+
+/// Deserializes a single resource, keeping whitelisted attributes as raw JSON.
+struct ResourceSeed<'a>(&'a AttributeWhitelist);
+
+impl<'de> DeserializeSeed<'de> for ResourceSeed<'_> {
+    type Value = ManifestNode;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<ManifestNode, D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de> Visitor<'de> for ResourceSeed<'_> {
+    type Value = ManifestNode;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a dbt resource")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<ManifestNode, A::Error> {
+        let mut node = ManifestNode::default();
+        let mut unique_id = None;
+        let mut resource_type = None;
+
+        // `null` is treated the same as a missing field, as dbt writes `null`
+        // for unset optional fields.
+        fn or_default<T: Default>(value: Option<T>) -> T {
+            value.unwrap_or_default()
+        }
+
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "unique_id" => unique_id = Some(map.next_value()?),
+                "resource_type" => resource_type = Some(map.next_value()?),
+                "name" => node.name = or_default(map.next_value()?),
+                "package_name" => node.package_name = or_default(map.next_value()?),
+                "original_file_path" => node.original_file_path = or_default(map.next_value()?),
+                "fqn" => node.fqn = or_default(map.next_value()?),
+                "tags" => node.tags = or_default(map.next_value()?),
+                "depends_on" => node.depends_on = or_default(map.next_value()?),
+                "test_metadata" => node.test_metadata = map.next_value()?,
+                "column_name" => node.column_name = map.next_value()?,
+                "attached_node" => node.attached_node = map.next_value()?,
+                "project" => node.project = map.next_value()?,
+                "group" => node.group = map.next_value()?,
+                "config" if self.0.contains("config") => {
+                    let config: Box<RawValue> = map.next_value()?;
+                    node.config =
+                        or_default(serde_json::from_str(config.get()).map_err(de::Error::custom)?);
+                    node.attributes.insert(key, config);
+                }
+                "config" => node.config = or_default(map.next_value()?),
+                "meta" if self.0.contains("meta") => {
+                    let meta: Box<RawValue> = map.next_value()?;
+                    node.meta =
+                        meta_values(serde_json::from_str(meta.get()).map_err(de::Error::custom)?);
+                    node.attributes.insert(key, meta);
+                }
+                "meta" => node.meta = meta_values(map.next_value()?),
+                attribute if self.0.contains(attribute) => {
+                    let value: Box<RawValue> = map.next_value()?;
+                    node.attributes.insert(key, value);
+                }
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+
+        node.unique_id = unique_id.ok_or_else(|| de::Error::missing_field("unique_id"))?;
+        node.resource_type =
+            resource_type.ok_or_else(|| de::Error::missing_field("resource_type"))?;
+
+        Ok(node)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"{
+        "nodes": {
+            "model.a": {"unique_id": "model.a", "resource_type": "model", "meta": {}},
+            "model.b": {"unique_id": "model.b", "resource_type": "model", "meta": {}},
+            "test.b_unique": {"unique_id": "test.b_unique", "resource_type": "test", "meta": {}}
+        },
+        "sources": {
+            "source.raw": {"unique_id": "source.raw", "resource_type": "source", "meta": {}}
+        },
+        "child_map": {
+            "source.raw": ["model.a"],
+            "model.a": ["model.b"],
+            "model.b": ["test.b_unique"],
+            "test.b_unique": []
+        },
+        "parent_map": {
+            "source.raw": [],
+            "model.a": ["source.raw"],
+            "model.b": ["model.a"],
+            "test.b_unique": ["model.b"]
+        }
+    }"#;
+
+    #[test]
+    fn manifest_to_graph_excludes_tests() {
+        let manifest = MANIFEST.parse::<Manifest>().unwrap();
+        let (nodes, edges) = manifest.to_graph().unwrap();
+
+        assert_eq!(nodes.len(), 3);
+        assert!(!nodes.iter().any(|node| node.id.starts_with("test")));
+        assert_eq!(edges.len(), 2);
+        assert_eq!(
+            HashSet::<Edge>::from_iter(edges),
+            HashSet::from_iter(vec![
+                Edge::new("source.raw", "model.a"),
+                Edge::new("model.a", "model.b"),
+            ])
+        );
+    }
+
+    #[test]
+    fn manifest_to_selected_graph() {
+        let manifest = MANIFEST.parse::<Manifest>().unwrap();
+        let selected = HashSet::from_iter(vec!["model.a".to_string(), "model.b".to_string()]);
+        let (nodes, edges) = manifest.to_selected_graph(&selected).unwrap();
+
+        assert_eq!(nodes.len(), 2);
+        assert_eq!(edges, vec![Edge::new("model.a", "model.b")]);
+    }
+
+    const FULL_MANIFEST: &str = r#"{
+        "nodes": {
+            "model.a": {"unique_id": "model.a", "resource_type": "model"}
+        },
+        "exposures": {
+            "exposure.dashboard": {
+                "unique_id": "exposure.dashboard", "resource_type": "exposure",
+                "depends_on": {"nodes": ["model.a", "metric.revenue"]}
+            }
+        },
+        "metrics": {
+            "metric.revenue": {
+                "unique_id": "metric.revenue", "resource_type": "metric",
+                "depends_on": {"nodes": ["semantic_model.orders"]}
+            }
+        },
+        "semantic_models": {
+            "semantic_model.orders": {
+                "unique_id": "semantic_model.orders", "resource_type": "semantic_model",
+                "depends_on": {"nodes": ["model.a"]}
+            }
+        },
+        "saved_queries": {
+            "saved_query.weekly": {
+                "unique_id": "saved_query.weekly", "resource_type": "saved_query",
+                "depends_on": {"nodes": ["metric.revenue"]}
+            }
+        },
+        "unit_tests": {
+            "unit_test.a.check": {
+                "unique_id": "unit_test.a.check", "resource_type": "unit_test",
+                "depends_on": {"nodes": ["model.a"]}
+            }
+        }
+    }"#;
+
+    #[test]
+    fn manifest_ingests_all_resource_types() {
+        let manifest = FULL_MANIFEST.parse::<Manifest>().unwrap();
+        let (nodes, edges) = manifest.to_graph().unwrap();
+
+        assert_eq!(nodes.len(), 6);
+        assert_eq!(
+            HashSet::<Edge>::from_iter(edges),
+            HashSet::from_iter(vec![
+                Edge::new("model.a", "exposure.dashboard"),
+                Edge::new("metric.revenue", "exposure.dashboard"),
+                Edge::new("semantic_model.orders", "metric.revenue"),
+                Edge::new("model.a", "semantic_model.orders"),
+                Edge::new("metric.revenue", "saved_query.weekly"),
+                Edge::new("model.a", "unit_test.a.check"),
+            ])
+        );
+    }
+
+    #[test]
+    fn manifest_resource_type_toggles() {
+        let manifest = FULL_MANIFEST.parse::<Manifest>().unwrap();
+        let options = ManifestOptions {
+            metrics: false,
+            unit_tests: false,
+            ..ManifestOptions::default()
+        };
+        let (nodes, edges) = manifest.to_graph_with_options(&options, None).unwrap();
+
+        let node_ids: HashSet<&str> = nodes.iter().map(|node| node.id.as_str()).collect();
+        assert_eq!(
+            node_ids,
+            HashSet::from_iter(vec![
+                "model.a",
+                "exposure.dashboard",
+                "semantic_model.orders",
+                "saved_query.weekly"
+            ])
+        );
+        assert_eq!(edges.len(), 2);
+    }
+
+    const TESTED_MANIFEST: &str = r#"{
+        "nodes": {
+            "model.orders": {"unique_id": "model.orders", "resource_type": "model"},
+            "model.customers": {"unique_id": "model.customers", "resource_type": "model"},
+            "test.unique_orders_id": {
+                "unique_id": "test.unique_orders_id", "resource_type": "test",
+                "name": "unique_orders_id", "column_name": "id",
+                "test_metadata": {"name": "unique"},
+                "config": {"severity": "ERROR"},
+                "attached_node": "model.orders",
+                "depends_on": {"nodes": ["model.orders"]}
+            },
+            "test.relationships_orders_customer_id": {
+                "unique_id": "test.relationships_orders_customer_id", "resource_type": "test",
+                "name": "relationships_orders_customer_id", "column_name": "customer_id",
+                "test_metadata": {"name": "relationships"},
+                "config": {"severity": "warn"},
+                "attached_node": "model.orders",
+                "depends_on": {"nodes": ["model.customers", "model.orders"]}
+            },
+            "test.assert_positive_totals": {
+                "unique_id": "test.assert_positive_totals", "resource_type": "test",
+                "name": "assert_positive_totals",
+                "depends_on": {"nodes": ["model.orders"]}
+            }
+        },
+        "child_map": {
+            "model.customers": ["model.orders", "test.relationships_orders_customer_id"],
+            "model.orders": [
+                "test.unique_orders_id",
+                "test.relationships_orders_customer_id",
+                "test.assert_positive_totals"
+            ]
+        }
+    }"#;
+
+    #[test]
+    fn manifest_fold_tests() {
+        let manifest = TESTED_MANIFEST.parse::<Manifest>().unwrap();
+        let options = ManifestOptions {
+            fold_tests: true,
+            ..ManifestOptions::default()
+        };
+        let (nodes, edges) = manifest.to_graph_with_options(&options, None).unwrap();
+
+        assert_eq!(nodes.len(), 2);
+        assert_eq!(edges, vec![Edge::new("model.customers", "model.orders")]);
+
+        let orders = nodes.iter().find(|node| node.id == "model.orders").unwrap();
+        assert_eq!(orders.test_count, 3);
+
+        let data: serde_json::Value = serde_json::from_str(&orders.data).unwrap();
+        let tests: Vec<FoldedTest> = serde_json::from_value(data["tests"].clone()).unwrap();
+        assert!(tests.contains(&FoldedTest {
+            name: "unique_orders_id".to_string(),
+            test_type: "unique".to_string(),
+            column: Some("id".to_string()),
+            severity: Some("ERROR".to_string()),
+        }));
+        assert!(tests.contains(&FoldedTest {
+            name: "assert_positive_totals".to_string(),
+            test_type: "singular".to_string(),
+            column: None,
+            severity: None,
+        }));
+
+        let customers = nodes
+            .iter()
+            .find(|node| node.id == "model.customers")
+            .unwrap();
+        assert_eq!(customers.test_count, 1);
+    }
+
+    #[test]
+    fn manifest_relationship_test_edges() {
+        let manifest = TESTED_MANIFEST.parse::<Manifest>().unwrap();
+        let (nodes, _) = manifest.to_graph().unwrap();
+
+        assert_eq!(nodes.iter().map(|node| node.test_count).sum::<usize>(), 0);
+        assert_eq!(
+            manifest.relationship_test_edges(&nodes),
+            vec![Edge::new("model.orders", "model.customers")]
+        );
+    }
+
+    #[test]
+    fn manifest_unknown_node_reference() {
+        let mut manifest = MANIFEST.parse::<Manifest>().unwrap();
+        manifest
+            .child_map
+            .insert("model.b".to_string(), vec!["model.missing".to_string()]);
+
+        match manifest.to_graph() {
+            Err(PowerGraphError::UnknownNode { id, referenced_by }) => {
+                assert_eq!(id, "model.missing");
+                assert_eq!(referenced_by, "model.b");
+            }
+            _ => panic!("expected an unknown node error"),
+        }
+    }
+
+    const CORE_MANIFEST: &str = r#"{
+        "metadata": {"project_name": "core"},
+        "nodes": {
+            "model.core.customers": {
+                "unique_id": "model.core.customers", "resource_type": "model",
+                "package_name": "core"
+            },
+            "model.evaluator.checks": {
+                "unique_id": "model.evaluator.checks", "resource_type": "model",
+                "package_name": "evaluator"
+            }
+        },
+        "child_map": {"model.core.customers": [], "model.evaluator.checks": []}
+    }"#;
+
+    const FINANCE_MANIFEST: &str = r#"{
+        "metadata": {"project_name": "finance"},
+        "nodes": {
+            "model.core.customers": {
+                "unique_id": "model.core.customers", "resource_type": "model",
+                "package_name": "core"
+            },
+            "model.finance.revenue": {
+                "unique_id": "model.finance.revenue", "resource_type": "model",
+                "package_name": "finance",
+                "depends_on": {"nodes": ["model.core.customers", "model.evaluator.checks"]}
+            },
+            "model.evaluator.checks": {
+                "unique_id": "model.evaluator.checks", "resource_type": "model",
+                "package_name": "evaluator"
+            }
+        },
+        "child_map": {
+            "model.core.customers": ["model.finance.revenue"],
+            "model.evaluator.checks": ["model.finance.revenue"],
+            "model.finance.revenue": []
+        }
+    }"#;
+
+    #[test]
+    fn manifest_merge_mesh() {
+        let manifests = vec![
+            CORE_MANIFEST.parse::<Manifest>().unwrap(),
+            FINANCE_MANIFEST.parse::<Manifest>().unwrap(),
+        ];
+        let manifest = Manifest::merge(manifests).unwrap();
+        let (nodes, edges) = manifest.to_graph().unwrap();
+
+        let projects: HashMap<&str, Option<&str>> = nodes
+            .iter()
+            .map(|node| (node.id.as_str(), node.project.as_deref()))
+            .collect();
+        assert_eq!(
+            projects,
+            HashMap::from_iter(vec![
+                ("core:model.core.customers", Some("core")),
+                ("core:model.evaluator.checks", Some("core")),
+                ("finance:model.evaluator.checks", Some("finance")),
+                ("finance:model.finance.revenue", Some("finance")),
+            ])
+        );
+        assert_eq!(
+            HashSet::<Edge>::from_iter(edges),
+            HashSet::from_iter(vec![
+                Edge::new("core:model.core.customers", "finance:model.finance.revenue"),
+                Edge::new(
+                    "finance:model.evaluator.checks",
+                    "finance:model.finance.revenue"
+                ),
+            ])
+        );
+
+        let selected =
+            crate::selector::select_nodes(&manifest, Some("project:finance"), None).unwrap();
+        assert_eq!(selected.len(), 2);
+    }
+
+    #[test]
+    fn manifest_merge_requires_distinct_projects() {
+        let manifests = vec![
+            CORE_MANIFEST.parse::<Manifest>().unwrap(),
+            CORE_MANIFEST.parse::<Manifest>().unwrap(),
+        ];
+        assert!(matches!(
+            Manifest::merge(manifests),
+            Err(PowerGraphError::InvalidMesh(_))
+        ));
+
+        let manifests = vec![
+            CORE_MANIFEST.parse::<Manifest>().unwrap(),
+            MANIFEST.parse::<Manifest>().unwrap(),
+        ];
+        assert!(matches!(
+            Manifest::merge(manifests),
+            Err(PowerGraphError::InvalidMesh(_))
+        ));
+    }
+
+    const ATTRIBUTE_MANIFEST: &str = r#"{
+        "metadata": {"dbt_version": "1.8.0"},
+        "macros": {"macro.a": {"macro_sql": "{% macro a() %}{% endmacro %}"}},
+        "nodes": {
+            "model.a": {
+                "unique_id": "model.a", "resource_type": "model",
+                "package_name": "jaffle", "fqn": ["jaffle", "a"], "tags": ["daily"],
+                "raw_code": "select 1", "description": null,
+                "meta": {"owner": "data", "tier": 1, "contacts": ["a@b.c"]},
+                "config": {"group": "finance", "severity": null, "materialized": "table"},
+                "columns": {"id": {"name": "id"}}
+            }
+        },
+        "sources": null,
+        "child_map": {"model.a": []}
+    }"#;
+
+    fn model_data(attributes: &AttributeWhitelist) -> serde_json::Value {
+        let manifest =
+            Manifest::from_reader_with_attributes(ATTRIBUTE_MANIFEST.as_bytes(), attributes)
+                .unwrap();
+        let (nodes, _) = manifest.to_graph().unwrap();
+        serde_json::from_str(&nodes[0].data).unwrap()
+    }
+
+    #[test]
+    fn manifest_attribute_whitelist() {
+        let data = model_data(&AttributeWhitelist::default());
+        assert_eq!(data["meta"]["owner"], "data");
+        assert_eq!(data["config"]["group"], "finance");
+        assert!(data.get("raw_code").is_none());
+        assert!(data.get("columns").is_none());
+
+        let data = model_data(&"raw_code, description".parse().unwrap());
+        assert_eq!(data["raw_code"], "select 1");
+        assert!(data["description"].is_null());
+        assert!(data.get("meta").is_none());
+        assert!(data.get("config").is_none());
+
+        let data = model_data(&"*".parse().unwrap());
+        assert_eq!(data["columns"]["id"]["name"], "id");
+        assert_eq!(data["unique_id"], "model.a");
+    }
+
+    #[test]
+    fn manifest_node_attributes() {
+        let expected = NodeAttributes::new()
+            .with_resource_type("model")
+            .with_package("jaffle")
+            .with_fqn(vec!["jaffle".to_string(), "a".to_string()])
+            .with_tags(vec!["daily".to_string()])
+            .with_materialization("table")
+            .with_owner("data")
+            .with("tier", "1");
+
+        for attributes in [
+            AttributeWhitelist::default(),
+            AttributeWhitelist::All,
+            "".parse().unwrap(),
+        ] {
+            let manifest =
+                Manifest::from_str_with_attributes(ATTRIBUTE_MANIFEST, &attributes).unwrap();
+            let (nodes, _) = manifest.to_graph().unwrap();
+            assert_eq!(nodes[0].get_attributes(), &expected);
+        }
+
+        let manifest = MANIFEST.parse::<Manifest>().unwrap();
+        let grouped = ManifestNode {
+            group: Some("finance".to_string()),
+            ..manifest.nodes["model.a"].clone()
+        };
+        assert_eq!(
+            grouped.node_attributes().owner(),
+            Some("finance".to_string())
+        );
+    }
+
+    #[test]
+    fn manifest_keeps_graph_fields_outside_whitelist() {
+        let manifest = Manifest::from_str_with_attributes(
+            TESTED_MANIFEST,
+            &AttributeWhitelist::Only(HashSet::new()),
+        )
+        .unwrap();
+
+        let severities: Vec<Option<String>> = manifest
+            .tests()
+            .map(|test| test.config.severity.clone())
+            .collect();
+        assert!(severities.contains(&Some("warn".to_string())));
+        assert!(manifest.tests().all(|test| test.attributes.is_empty()));
+    }
+
+    #[test]
+    fn manifest_malformed_json() {
+        assert!(matches!(
+            "{\"nodes\": ".parse::<Manifest>(),
+            Err(PowerGraphError::Parse(_))
+        ));
+    }
+}
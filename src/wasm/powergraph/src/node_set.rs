@@ -0,0 +1,237 @@
+//! Sets of interned nodes, stored as bitsets.
+
+use std::cmp::Ordering;
+
+use crate::interner::NodeIndex;
+
+const WORD_BITS: usize = u64::BITS as usize;
+
+/// A set of nodes by their interned index, stored as a bitset so that set
+/// operations on clusters are a pass over machine words. Trailing empty words
+/// are trimmed, so that equal sets have equal words.
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
+pub struct NodeSet {
+    words: Vec<u64>,
+}
+
+impl NodeSet {
+    pub fn new() -> NodeSet {
+        NodeSet { words: Vec::new() }
+    }
+
+    pub fn single(node: NodeIndex) -> NodeSet {
+        let mut set = NodeSet::new();
+        set.insert(node);
+        set
+    }
+
+    fn from_words(mut words: Vec<u64>) -> NodeSet {
+        while words.last() == Some(&0) {
+            words.pop();
+        }
+        NodeSet { words }
+    }
+
+    pub fn insert(&mut self, node: NodeIndex) {
+        let (word, bit) = position(node);
+        if word >= self.words.len() {
+            self.words.resize(word + 1, 0);
+        }
+        self.words[word] |= bit;
+    }
+
+    pub fn contains(&self, node: NodeIndex) -> bool {
+        let (word, bit) = position(node);
+        self.words.get(word).is_some_and(|word| word & bit != 0)
+    }
+
+    pub fn len(&self) -> usize {
+        self.words
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    pub fn union(&self, other: &NodeSet) -> NodeSet {
+        let mut set = self.clone();
+        set.union_with(other);
+        set
+    }
+
+    pub fn union_with(&mut self, other: &NodeSet) {
+        if other.words.len() > self.words.len() {
+            self.words.resize(other.words.len(), 0);
+        }
+        for (word, other_word) in self.words.iter_mut().zip(other.words.iter()) {
+            *word |= other_word;
+        }
+    }
+
+    pub fn intersection(&self, other: &NodeSet) -> NodeSet {
+        NodeSet::from_words(
+            self.words
+                .iter()
+                .zip(other.words.iter())
+                .map(|(word, other_word)| word & other_word)
+                .collect(),
+        )
+    }
+
+    pub fn difference(&self, other: &NodeSet) -> NodeSet {
+        NodeSet::from_words(
+            self.words
+                .iter()
+                .enumerate()
+                .map(|(index, word)| word & !other.words.get(index).unwrap_or(&0))
+                .collect(),
+        )
+    }
+
+    /// The number of members shared with `other`, without building the
+    /// intersection.
+    pub fn intersection_len(&self, other: &NodeSet) -> usize {
+        self.words
+            .iter()
+            .zip(other.words.iter())
+            .map(|(word, other_word)| (word & other_word).count_ones() as usize)
+            .sum()
+    }
+
+    pub fn is_disjoint(&self, other: &NodeSet) -> bool {
+        self.words
+            .iter()
+            .zip(other.words.iter())
+            .all(|(word, other_word)| word & other_word == 0)
+    }
+
+    pub fn is_subset_of(&self, other: &NodeSet) -> bool {
+        self.words.len() <= other.words.len()
+            && self
+                .words
+                .iter()
+                .zip(other.words.iter())
+                .all(|(word, other_word)| word & !other_word == 0)
+    }
+
+    pub fn is_proper_subset_of(&self, other: &NodeSet) -> bool {
+        self != other && self.is_subset_of(other)
+    }
+
+    /// The members of the set in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = NodeIndex> + Clone + '_ {
+        self.words.iter().enumerate().flat_map(|(index, &word)| {
+            Bits { word }.map(move |bit| (index * WORD_BITS + bit) as NodeIndex)
+        })
+    }
+}
+
+fn position(node: NodeIndex) -> (usize, u64) {
+    let node = node as usize;
+    (node / WORD_BITS, 1 << (node % WORD_BITS))
+}
+
+/// The set bits of a word, lowest first.
+#[derive(Clone)]
+struct Bits {
+    word: u64,
+}
+
+impl Iterator for Bits {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        if self.word == 0 {
+            return None;
+        }
+        let bit = self.word.trailing_zeros() as usize;
+        self.word &= self.word - 1;
+        Some(bit)
+    }
+}
+
+impl FromIterator<NodeIndex> for NodeSet {
+    fn from_iter<I: IntoIterator<Item = NodeIndex>>(nodes: I) -> NodeSet {
+        let mut set = NodeSet::new();
+        for node in nodes {
+            set.insert(node);
+        }
+        set
+    }
+}
+
+/// Sets are ordered by their members in ascending order, as a `BTreeSet`
+/// would be, so that sets of interned ids are ordered as the sets of ids.
+impl PartialOrd for NodeSet {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for NodeSet {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.iter().cmp(other.iter())
+    }
+}
+
+impl std::fmt::Debug for NodeSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(nodes: &[NodeIndex]) -> NodeSet {
+        nodes.iter().copied().collect()
+    }
+
+    #[test]
+    fn set_operations() {
+        let one = set(&[1, 2, 70]);
+        let two = set(&[2, 3, 4]);
+
+        assert_eq!(one.union(&two), set(&[1, 2, 3, 4, 70]));
+        assert_eq!(one.intersection(&two), set(&[2]));
+        assert_eq!(one.difference(&two), set(&[1, 70]));
+        assert_eq!(two.difference(&one), set(&[3, 4]));
+        assert_eq!(one.intersection_len(&two), 1);
+        assert_eq!(one.len(), 3);
+        assert!(one.contains(70));
+        assert!(!one.contains(3));
+        assert!(!one.is_disjoint(&two));
+        assert!(set(&[1]).is_disjoint(&two));
+    }
+
+    #[test]
+    fn empty_words_are_trimmed() {
+        let high = set(&[1, 100]);
+        let difference = high.difference(&set(&[100]));
+
+        assert_eq!(difference, set(&[1]));
+        assert!(set(&[100]).intersection(&set(&[1])).is_empty());
+        assert!(difference.is_subset_of(&set(&[1, 2])));
+    }
+
+    #[test]
+    fn subsets() {
+        assert!(set(&[1, 2]).is_subset_of(&set(&[1, 2, 3, 4])));
+        assert!(!set(&[1, 2]).is_subset_of(&set(&[1, 3, 4])));
+        assert!(!set(&[1, 200]).is_subset_of(&set(&[1, 2])));
+        assert!(set(&[1, 2]).is_proper_subset_of(&set(&[1, 2, 3])));
+        assert!(!set(&[1, 2]).is_proper_subset_of(&set(&[1, 2])));
+    }
+
+    #[test]
+    fn ordered_by_members() {
+        assert_eq!(set(&[70, 2, 1]).iter().collect::<Vec<_>>(), vec![1, 2, 70]);
+        assert!(set(&[1, 70]) < set(&[2]));
+        assert!(set(&[1]) < set(&[1, 2]));
+        assert!(set(&[]) < set(&[0]));
+    }
+}
@@ -0,0 +1,205 @@
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::error::PowerGraphError;
+use crate::similarity::{NeighborDirection, SimilarityMeasure};
+
+/// How power edge candidates are ranked before they are processed. Candidates
+/// with higher scores are processed first.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CandidateScoring {
+    /// The number of edges the candidate would cover.
+    #[default]
+    Edges,
+    /// The number of nodes in the candidate's source and target.
+    Nodes,
+}
+
+/// Parameters for `PowerGraph::decompose_with_options`.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct DecompositionOptions {
+    /// The minimum similarity for two clusters to be merged, and for a
+    /// cluster's neighborhood to be added as a cluster.
+    pub similarity_threshold: f32,
+    /// How the similarity of two clusters is measured.
+    pub similarity: SimilarityMeasure,
+    /// Which neighbors of two clusters are compared for similarity.
+    pub neighbor_direction: NeighborDirection,
+    /// The number of rounds of neighborhood expansion.
+    pub neighborhood_depth: usize,
+    /// The minimum number of members of a power node. Singletons are always
+    /// kept, so this only applies to power nodes with more than one member.
+    pub min_power_node_size: usize,
+    /// The maximum number of members of a power node, if any.
+    pub max_power_node_size: Option<usize>,
+    /// Whether power edges from a power node to itself are allowed.
+    pub allow_cliques: bool,
+    /// Whether to respect the direction of edges, so that every power edge
+    /// means every node of its source has an edge to every node of its target.
+    pub directed: bool,
+    /// The number of edges a power edge may be missing in a lossy
+    /// decomposition. Missing edges are recorded as negative edges.
+    pub max_missing_edges: usize,
+    /// The fraction of its edges a power edge may be missing in a lossy
+    /// decomposition, as an alternative to `max_missing_edges`.
+    pub max_missing_ratio: f32,
+    /// How power edge candidates are ranked, and so the order in which they
+    /// are turned into power edges.
+    pub scoring: CandidateScoring,
+}
+
+impl Default for DecompositionOptions {
+    fn default() -> Self {
+        DecompositionOptions {
+            similarity_threshold: 0.25,
+            similarity: SimilarityMeasure::Jaccard,
+            neighbor_direction: NeighborDirection::Any,
+            neighborhood_depth: 2,
+            min_power_node_size: 2,
+            max_power_node_size: None,
+            allow_cliques: true,
+            directed: false,
+            max_missing_edges: 0,
+            max_missing_ratio: 0.0,
+            scoring: CandidateScoring::Edges,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl DecompositionOptions {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> DecompositionOptions {
+        DecompositionOptions::default()
+    }
+}
+
+impl DecompositionOptions {
+    /// Check that the options are within their valid ranges.
+    pub fn validate(&self) -> Result<(), PowerGraphError> {
+        let invalid = |message: String| Err(PowerGraphError::InvalidOptions(message));
+
+        if !(0.0..=1.0).contains(&self.similarity_threshold) {
+            return invalid(format!(
+                "the similarity threshold must be between 0 and 1, not {}",
+                self.similarity_threshold
+            ));
+        }
+        if !(0.0..1.0).contains(&self.max_missing_ratio) {
+            return invalid(format!(
+                "the maximum missing edge ratio must be at least 0 and less than 1, not {}",
+                self.max_missing_ratio
+            ));
+        }
+        if self.min_power_node_size < 2 {
+            return invalid("the minimum power node size must be at least 2".to_string());
+        }
+        if let Some(max) = self.max_power_node_size {
+            if max < self.min_power_node_size {
+                return invalid(format!(
+                    "the maximum power node size ({}) is less than the minimum ({})",
+                    max, self.min_power_node_size
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Whether power edges may be missing some of their edges.
+    pub(crate) fn is_lossy(&self) -> bool {
+        self.max_missing_edges > 0 || self.max_missing_ratio > 0.0
+    }
+
+    /// Whether a power edge between nodes with `possible` pairs may be missing
+    /// `missing` of its edges. Power edges always have more edges than they
+    /// are missing, whatever the options.
+    pub(crate) fn allows_missing(&self, missing: usize, possible: usize) -> bool {
+        missing == 0
+            || (missing < possible - missing
+                && (missing <= self.max_missing_edges
+                    || missing as f32 <= self.max_missing_ratio * possible as f32))
+    }
+
+    /// Whether a cluster of `size` members may become a power node.
+    pub(crate) fn allows_size(&self, size: usize) -> bool {
+        size <= 1
+            || (size >= self.min_power_node_size
+                && self.max_power_node_size.is_none_or(|max| size <= max))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_options() {
+        assert!(DecompositionOptions::default().validate().is_ok());
+
+        let invalid = [
+            DecompositionOptions {
+                similarity_threshold: 1.5,
+                ..Default::default()
+            },
+            DecompositionOptions {
+                min_power_node_size: 1,
+                ..Default::default()
+            },
+            DecompositionOptions {
+                max_missing_ratio: 1.0,
+                ..Default::default()
+            },
+            DecompositionOptions {
+                min_power_node_size: 4,
+                max_power_node_size: Some(3),
+                ..Default::default()
+            },
+        ];
+        for options in invalid {
+            assert!(matches!(
+                options.validate(),
+                Err(PowerGraphError::InvalidOptions(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn allowed_missing_edges() {
+        let lossless = DecompositionOptions::default();
+        assert!(!lossless.is_lossy());
+        assert!(lossless.allows_missing(0, 4));
+        assert!(!lossless.allows_missing(1, 4));
+
+        let count = DecompositionOptions {
+            max_missing_edges: 2,
+            ..Default::default()
+        };
+        assert!(count.allows_missing(2, 20));
+        assert!(!count.allows_missing(3, 20));
+        // At least half of the edges must be present.
+        assert!(!count.allows_missing(2, 4));
+
+        let ratio = DecompositionOptions {
+            max_missing_ratio: 0.1,
+            ..Default::default()
+        };
+        assert!(ratio.allows_missing(2, 20));
+        assert!(!ratio.allows_missing(3, 20));
+    }
+
+    #[test]
+    fn allowed_sizes() {
+        let options = DecompositionOptions {
+            min_power_node_size: 3,
+            max_power_node_size: Some(4),
+            ..Default::default()
+        };
+
+        let allowed: Vec<usize> = (1..=5).filter(|size| options.allows_size(*size)).collect();
+        assert_eq!(allowed, vec![1, 3, 4]);
+    }
+}
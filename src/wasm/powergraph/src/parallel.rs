@@ -0,0 +1,72 @@
+//! Work over many independent items, on several threads with the `parallel`
+//! feature and on this thread without it. Results are in the order of the
+//! items either way, so a decomposition is the same with or without threads.
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+/// The number of items, such as cluster pairs, a decomposition processes as
+/// one unit of work, in each connected component when merging clusters.
+/// Without threads each item is its own unit, so that a `Decomposition` can be
+/// stepped in small slices.
+#[cfg(feature = "parallel")]
+pub const BATCH: usize = 1024;
+#[cfg(not(feature = "parallel"))]
+pub const BATCH: usize = 1;
+
+/// `f` applied to each of `items`, in order.
+#[cfg(feature = "parallel")]
+pub fn map<T: Send, R: Send>(items: Vec<T>, f: impl Fn(T) -> R + Sync + Send) -> Vec<R> {
+    items.into_par_iter().map(f).collect()
+}
+
+#[cfg(not(feature = "parallel"))]
+pub fn map<T, R>(items: Vec<T>, f: impl Fn(T) -> R) -> Vec<R> {
+    items.into_iter().map(f).collect()
+}
+
+/// `f` applied to each of `items` in place.
+#[cfg(feature = "parallel")]
+pub fn for_each_mut<T: Send>(items: &mut [T], f: impl Fn(&mut T) + Sync + Send) {
+    items.par_iter_mut().for_each(f)
+}
+
+#[cfg(not(feature = "parallel"))]
+pub fn for_each_mut<T>(items: &mut [T], f: impl Fn(&mut T)) {
+    items.iter_mut().for_each(f)
+}
+
+/// The first of `items` for which `predicate` holds.
+#[cfg(feature = "parallel")]
+pub fn find_first<T: Sync>(
+    items: &[T],
+    predicate: impl Fn(&T) -> bool + Sync + Send,
+) -> Option<&T> {
+    items.par_iter().find_first(|item| predicate(item))
+}
+
+#[cfg(not(feature = "parallel"))]
+pub fn find_first<T>(items: &[T], predicate: impl Fn(&T) -> bool) -> Option<&T> {
+    items.iter().find(|item| predicate(item))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn results_in_item_order() {
+        let items: Vec<usize> = (0..10_000).collect();
+
+        assert_eq!(
+            map(items.clone(), |item| item * 2),
+            (0..20_000).step_by(2).collect::<Vec<_>>()
+        );
+        assert_eq!(find_first(&items, |item| item % 1000 == 999), Some(&999));
+        assert_eq!(find_first(&items, |item| *item > 10_000), None);
+
+        let mut items = items;
+        for_each_mut(&mut items, |item| *item += 1);
+        assert_eq!(items, (1..10_001).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,149 @@
+//! Progress reporting and cancellation for long-running decompositions.
+
+use wasm_bindgen::JsValue;
+use web_sys::AbortSignal;
+
+use crate::error::PowerGraphError;
+
+/// The number of items processed between progress reports, so that reporting
+/// does not dominate the loops it reports on.
+const REPORT_INTERVAL: usize = 100;
+
+/// Observes a decomposition as it runs, and may cancel it.
+pub trait Progress {
+    /// Report that `processed` of `total` items of a phase have been processed.
+    /// The total of a phase may grow as it finds more work.
+    fn report(&mut self, phase: &str, processed: usize, total: usize);
+
+    /// Whether the decomposition should stop as soon as possible.
+    fn is_cancelled(&self) -> bool {
+        false
+    }
+}
+
+/// Ignores progress and never cancels.
+pub(crate) struct NoProgress;
+
+impl Progress for NoProgress {
+    fn report(&mut self, _phase: &str, _processed: usize, _total: usize) {}
+}
+
+/// Reports progress to a JS callback, called with the phase name, the items
+/// processed and the total, and cancels once an `AbortSignal` is aborted.
+pub(crate) struct JsProgress {
+    pub(crate) callback: Option<js_sys::Function>,
+    pub(crate) signal: Option<AbortSignal>,
+}
+
+impl Progress for JsProgress {
+    fn report(&mut self, phase: &str, processed: usize, total: usize) {
+        if let Some(callback) = &self.callback {
+            // An exception thrown by the callback should not abandon the
+            // decomposition; it can abort the signal to do that.
+            let _ = callback.call3(
+                &JsValue::NULL,
+                &JsValue::from_str(phase),
+                &JsValue::from(processed as u32),
+                &JsValue::from(total as u32),
+            );
+        }
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.signal.as_ref().is_some_and(AbortSignal::aborted)
+    }
+}
+
+/// Report progress at the start and end of a phase, and whenever `processed`
+/// passes a multiple of `REPORT_INTERVAL` since `previous`, the items processed
+/// at the last checkpoint, so that batches of items are reported too. Fails
+/// with `PowerGraphError::Cancelled` if the decomposition has been cancelled.
+pub(crate) fn checkpoint(
+    progress: &mut dyn Progress,
+    phase: &str,
+    previous: usize,
+    processed: usize,
+    total: usize,
+) -> Result<(), PowerGraphError> {
+    let crossed = processed / REPORT_INTERVAL > previous / REPORT_INTERVAL;
+    if processed > 0 && !crossed && processed < total {
+        return Ok(());
+    }
+
+    progress.report(phase, processed, total);
+    if progress.is_cancelled() {
+        return Err(PowerGraphError::Cancelled);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Records every report, and cancels once it has `cancel_after` reports.
+    #[derive(Default)]
+    pub(crate) struct Recorder {
+        pub(crate) reports: Vec<(String, usize, usize)>,
+        pub(crate) cancel_after: Option<usize>,
+    }
+
+    impl Progress for Recorder {
+        fn report(&mut self, phase: &str, processed: usize, total: usize) {
+            self.reports.push((phase.to_string(), processed, total));
+        }
+
+        fn is_cancelled(&self) -> bool {
+            self.cancel_after
+                .is_some_and(|reports| self.reports.len() >= reports)
+        }
+    }
+
+    #[test]
+    fn checkpoints_report_at_intervals() {
+        let mut recorder = Recorder::default();
+        for processed in 0..=250_usize {
+            checkpoint(
+                &mut recorder,
+                "candidates",
+                processed.saturating_sub(1),
+                processed,
+                250,
+            )
+            .unwrap();
+        }
+
+        let processed: Vec<usize> = recorder.reports.iter().map(|report| report.1).collect();
+        assert_eq!(processed, vec![0, 100, 200, 250]);
+    }
+
+    #[test]
+    fn checkpoints_report_batches() {
+        let mut recorder = Recorder::default();
+        let mut previous = 0;
+        for processed in [0, 64, 128, 192, 256, 300] {
+            checkpoint(&mut recorder, "candidates", previous, processed, 300).unwrap();
+            previous = processed;
+        }
+
+        // Batches that never land on a multiple of the interval are reported
+        // once they pass one.
+        let processed: Vec<usize> = recorder.reports.iter().map(|report| report.1).collect();
+        assert_eq!(processed, vec![0, 128, 256, 300]);
+    }
+
+    #[test]
+    fn checkpoints_cancel() {
+        let mut recorder = Recorder {
+            cancel_after: Some(2),
+            ..Default::default()
+        };
+
+        assert!(checkpoint(&mut recorder, "similarity", 0, 0, 500).is_ok());
+        assert!(checkpoint(&mut recorder, "similarity", 0, 50, 500).is_ok());
+        assert!(matches!(
+            checkpoint(&mut recorder, "similarity", 50, 150, 500),
+            Err(PowerGraphError::Cancelled)
+        ));
+    }
+}
@@ -0,0 +1,462 @@
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::str::FromStr;
+
+use crate::{
+    error::PowerGraphError,
+    manifest::{Manifest, ManifestNode},
+};
+
+/// How a selector criterion matches against a `ManifestNode`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SelectorMethod {
+    /// A bare selector. Matches the unique_id, name, fqn or file path.
+    Default,
+    Tag,
+    Path,
+    ResourceType,
+    Package,
+    Fqn,
+    UniqueId,
+    /// The dbt project of a resource in merged dbt Mesh manifests.
+    Project,
+}
+
+impl FromStr for SelectorMethod {
+    type Err = PowerGraphError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "tag" => Ok(SelectorMethod::Tag),
+            "path" => Ok(SelectorMethod::Path),
+            "resource_type" => Ok(SelectorMethod::ResourceType),
+            "package" => Ok(SelectorMethod::Package),
+            "fqn" => Ok(SelectorMethod::Fqn),
+            "unique_id" => Ok(SelectorMethod::UniqueId),
+            "project" => Ok(SelectorMethod::Project),
+            _ => Err(PowerGraphError::InvalidSelector(format!(
+                "unknown selector method `{}`",
+                value
+            ))),
+        }
+    }
+}
+
+/// A single selector criterion, such as `2+tag:finance+` or `@model.orders`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SelectorCriterion {
+    method: SelectorMethod,
+    value: String,
+    /// Include ancestors up to the given depth. `Some(None)` is unbounded.
+    parents: Option<Option<usize>>,
+    /// Include descendants up to the given depth. `Some(None)` is unbounded.
+    children: Option<Option<usize>>,
+    /// Include descendants and all of their ancestors.
+    children_and_parents: bool,
+}
+
+impl FromStr for SelectorCriterion {
+    type Err = PowerGraphError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || PowerGraphError::InvalidSelector(format!("invalid selector `{}`", value));
+
+        let (children_and_parents, rest) = match value.strip_prefix('@') {
+            Some(rest) => (true, rest),
+            None => (false, value),
+        };
+
+        let (parents, rest) = match rest.find('+') {
+            Some(index) if rest[..index].chars().all(|c| c.is_ascii_digit()) => {
+                let depth = &rest[..index];
+                let depth = if depth.is_empty() {
+                    None
+                } else {
+                    Some(depth.parse::<usize>().map_err(|_| invalid())?)
+                };
+                (Some(depth), &rest[index + 1..])
+            }
+            _ => (None, rest),
+        };
+
+        let (children, rest) = match rest.rfind('+') {
+            Some(index) if rest[index + 1..].chars().all(|c| c.is_ascii_digit()) => {
+                let depth = &rest[index + 1..];
+                let depth = if depth.is_empty() {
+                    None
+                } else {
+                    Some(depth.parse::<usize>().map_err(|_| invalid())?)
+                };
+                (Some(depth), &rest[..index])
+            }
+            _ => (None, rest),
+        };
+
+        if children_and_parents && parents.is_some() {
+            return Err(invalid());
+        }
+
+        let (method, value) = match rest.split_once(':') {
+            Some((method, value)) => (method.parse::<SelectorMethod>()?, value),
+            None => (SelectorMethod::Default, rest),
+        };
+
+        if value.is_empty() {
+            return Err(invalid());
+        }
+
+        Ok(SelectorCriterion {
+            method,
+            value: value.to_string(),
+            parents,
+            children,
+            children_and_parents,
+        })
+    }
+}
+
+impl SelectorCriterion {
+    fn matches(&self, node: &ManifestNode) -> bool {
+        match self.method {
+            SelectorMethod::Tag => node.tags.iter().any(|tag| glob_match(&self.value, tag)),
+            SelectorMethod::Path => path_match(&self.value, &node.original_file_path),
+            SelectorMethod::ResourceType => node.resource_type == self.value,
+            SelectorMethod::Package => glob_match(&self.value, &node.package_name),
+            SelectorMethod::Fqn => fqn_match(&self.value, node),
+            SelectorMethod::UniqueId => glob_match(&self.value, &node.unique_id),
+            SelectorMethod::Project => node
+                .project
+                .as_ref()
+                .is_some_and(|project| glob_match(&self.value, project)),
+            SelectorMethod::Default => {
+                if self.value.contains('/') || self.value.ends_with(".sql") {
+                    path_match(&self.value, &node.original_file_path)
+                } else {
+                    glob_match(&self.value, &node.unique_id) || fqn_match(&self.value, node)
+                }
+            }
+        }
+    }
+
+    fn select(&self, manifest: &Manifest) -> HashSet<String> {
+        let matched: HashSet<String> = manifest
+            .resources()
+            .filter(|node| self.matches(node))
+            .map(|node| node.unique_id.clone())
+            .collect();
+
+        let mut selected = matched.clone();
+
+        if let Some(depth) = self.parents {
+            selected.extend(traverse(&manifest.parent_map, &matched, depth));
+        }
+
+        if self.children_and_parents {
+            let descendants = traverse(&manifest.child_map, &matched, None);
+            selected.extend(traverse(&manifest.parent_map, &descendants, None));
+            selected.extend(descendants);
+        } else if let Some(depth) = self.children {
+            selected.extend(traverse(&manifest.child_map, &matched, depth));
+        }
+
+        selected
+    }
+}
+
+/// A dbt-style selection. Whitespace separated groups are unioned, and comma
+/// separated criteria within a group are intersected.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Selector {
+    groups: Vec<Vec<SelectorCriterion>>,
+}
+
+impl FromStr for Selector {
+    type Err = PowerGraphError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let groups = value
+            .split_whitespace()
+            .map(|group| {
+                group
+                    .split(',')
+                    .map(|criterion| criterion.parse::<SelectorCriterion>())
+                    .collect::<Result<Vec<SelectorCriterion>, PowerGraphError>>()
+            })
+            .collect::<Result<Vec<Vec<SelectorCriterion>>, PowerGraphError>>()?;
+
+        if groups.is_empty() {
+            return Err(PowerGraphError::InvalidSelector(
+                "empty selector".to_string(),
+            ));
+        }
+
+        Ok(Selector { groups })
+    }
+}
+
+impl Selector {
+    /// Return the unique_ids of all manifest resources matched by this selector.
+    pub fn select(&self, manifest: &Manifest) -> HashSet<String> {
+        self.groups
+            .iter()
+            .flat_map(|group| {
+                group
+                    .iter()
+                    .map(|criterion| criterion.select(manifest))
+                    .reduce(|acc, selected| acc.intersection(&selected).cloned().collect())
+                    .unwrap_or_default()
+            })
+            .collect()
+    }
+}
+
+/// Select nodes from a manifest with a dbt-style `select` and `exclude`
+/// expression. Without a `select` expression, every node is selected.
+pub fn select_nodes(
+    manifest: &Manifest,
+    select: Option<&str>,
+    exclude: Option<&str>,
+) -> Result<HashSet<String>, PowerGraphError> {
+    let mut selected: HashSet<String> = match select {
+        Some(select) => select.parse::<Selector>()?.select(manifest),
+        None => manifest
+            .resources()
+            .map(|resource| resource.unique_id.clone())
+            .collect(),
+    };
+
+    if let Some(exclude) = exclude {
+        let excluded = exclude.parse::<Selector>()?.select(manifest);
+        selected.retain(|id| !excluded.contains(id));
+    }
+
+    Ok(selected)
+}
+
+/// Breadth-first traversal of `adjacency` from `start`, up to an optional depth.
+/// The starting nodes are not included in the result.
+fn traverse(
+    adjacency: &BTreeMap<String, Vec<String>>,
+    start: &HashSet<String>,
+    depth: Option<usize>,
+) -> HashSet<String> {
+    let mut visited: HashSet<String> = HashSet::new();
+    let mut queue: VecDeque<(&String, usize)> = start.iter().map(|id| (id, 0)).collect();
+
+    while let Some((id, distance)) = queue.pop_front() {
+        if depth.is_some_and(|depth| distance >= depth) {
+            continue;
+        }
+
+        for next in adjacency.get(id).into_iter().flatten() {
+            if !start.contains(next) && visited.insert(next.clone()) {
+                queue.push_back((next, distance + 1));
+            }
+        }
+    }
+
+    visited
+}
+
+fn path_match(pattern: &str, path: &str) -> bool {
+    let pattern = pattern.trim_end_matches('/');
+    path == pattern || path.starts_with(&format!("{}/", pattern)) || glob_match(pattern, path)
+}
+
+fn fqn_match(pattern: &str, node: &ManifestNode) -> bool {
+    if glob_match(pattern, &node.name) {
+        return true;
+    }
+
+    let segments: Vec<&str> = pattern.split('.').collect();
+    let prefix_match = |fqn: &[String]| {
+        fqn.len() >= segments.len()
+            && segments
+                .iter()
+                .zip(fqn.iter())
+                .all(|(segment, part)| glob_match(segment, part))
+    };
+
+    // Match with or without the leading package name.
+    prefix_match(&node.fqn) || (node.fqn.len() > 1 && prefix_match(&node.fqn[1..]))
+}
+
+/// Match `value` against a pattern where `*` matches any run of characters
+/// and `?` matches a single character.
+fn glob_match(pattern: &str, value: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let value: Vec<char> = value.chars().collect();
+
+    let (mut p, mut v) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while v < value.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == value[v]) {
+            p += 1;
+            v += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, v));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            p = star + 1;
+            v = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"{
+        "nodes": {
+            "seed.revenue.seed_accounts": {
+                "unique_id": "seed.revenue.seed_accounts", "resource_type": "seed",
+                "name": "seed_accounts", "package_name": "revenue",
+                "original_file_path": "seeds/seed_accounts.csv",
+                "fqn": ["revenue", "seed_accounts"], "tags": []
+            },
+            "model.revenue.stg_accounts": {
+                "unique_id": "model.revenue.stg_accounts", "resource_type": "model",
+                "name": "stg_accounts", "package_name": "revenue",
+                "original_file_path": "models/staging/stg_accounts.sql",
+                "fqn": ["revenue", "staging", "stg_accounts"], "tags": ["staging"]
+            },
+            "model.revenue.accounts": {
+                "unique_id": "model.revenue.accounts", "resource_type": "model",
+                "name": "accounts", "package_name": "revenue",
+                "original_file_path": "models/marts/accounts.sql",
+                "fqn": ["revenue", "marts", "accounts"], "tags": ["finance"]
+            },
+            "model.revenue.revenue": {
+                "unique_id": "model.revenue.revenue", "resource_type": "model",
+                "name": "revenue", "package_name": "revenue",
+                "original_file_path": "models/marts/revenue.sql",
+                "fqn": ["revenue", "marts", "revenue"], "tags": ["finance", "daily"]
+            },
+            "model.billing.invoices": {
+                "unique_id": "model.billing.invoices", "resource_type": "model",
+                "name": "invoices", "package_name": "billing",
+                "original_file_path": "models/invoices.sql",
+                "fqn": ["billing", "invoices"], "tags": ["finance"]
+            }
+        },
+        "child_map": {
+            "seed.revenue.seed_accounts": ["model.revenue.stg_accounts"],
+            "model.revenue.stg_accounts": ["model.revenue.accounts"],
+            "model.revenue.accounts": ["model.revenue.revenue"],
+            "model.revenue.revenue": [],
+            "model.billing.invoices": ["model.revenue.revenue"]
+        },
+        "parent_map": {
+            "seed.revenue.seed_accounts": [],
+            "model.revenue.stg_accounts": ["seed.revenue.seed_accounts"],
+            "model.revenue.accounts": ["model.revenue.stg_accounts"],
+            "model.revenue.revenue": ["model.revenue.accounts", "model.billing.invoices"],
+            "model.billing.invoices": []
+        }
+    }"#;
+
+    fn select(select: &str, exclude: Option<&str>) -> HashSet<String> {
+        let manifest = MANIFEST.parse::<Manifest>().unwrap();
+        select_nodes(&manifest, Some(select), exclude).unwrap()
+    }
+
+    fn ids(values: &[&str]) -> HashSet<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn select_by_method() {
+        assert_eq!(
+            select("tag:finance", None),
+            ids(&[
+                "model.revenue.accounts",
+                "model.revenue.revenue",
+                "model.billing.invoices"
+            ])
+        );
+        assert_eq!(
+            select("path:models/marts", None),
+            ids(&["model.revenue.accounts", "model.revenue.revenue"])
+        );
+        assert_eq!(
+            select("resource_type:seed", None),
+            ids(&["seed.revenue.seed_accounts"])
+        );
+        assert_eq!(
+            select("package:billing", None),
+            ids(&["model.billing.invoices"])
+        );
+        assert_eq!(
+            select("marts.*", None),
+            ids(&["model.revenue.accounts", "model.revenue.revenue"])
+        );
+    }
+
+    #[test]
+    fn select_graph_operators() {
+        assert_eq!(
+            select("+model.revenue.accounts", None),
+            ids(&[
+                "seed.revenue.seed_accounts",
+                "model.revenue.stg_accounts",
+                "model.revenue.accounts"
+            ])
+        );
+        assert_eq!(
+            select("1+accounts+", None),
+            ids(&[
+                "model.revenue.stg_accounts",
+                "model.revenue.accounts",
+                "model.revenue.revenue"
+            ])
+        );
+        assert_eq!(
+            select("@stg_accounts", None),
+            ids(&[
+                "seed.revenue.seed_accounts",
+                "model.revenue.stg_accounts",
+                "model.revenue.accounts",
+                "model.revenue.revenue",
+                "model.billing.invoices"
+            ])
+        );
+    }
+
+    #[test]
+    fn select_union_intersection_and_exclude() {
+        assert_eq!(
+            select("tag:finance,package:revenue", None),
+            ids(&["model.revenue.accounts", "model.revenue.revenue"])
+        );
+        assert_eq!(
+            select("resource_type:seed tag:daily", None),
+            ids(&["seed.revenue.seed_accounts", "model.revenue.revenue"])
+        );
+        assert_eq!(
+            select("tag:finance", Some("package:billing tag:daily")),
+            ids(&["model.revenue.accounts"])
+        );
+    }
+
+    #[test]
+    fn invalid_selectors() {
+        assert!("color:blue".parse::<Selector>().is_err());
+        assert!("tag:".parse::<Selector>().is_err());
+        assert!("@+model".parse::<Selector>().is_err());
+        assert!("  ".parse::<Selector>().is_err());
+    }
+
+    #[test]
+    fn glob_matching() {
+        assert!(glob_match("stg_*", "stg_accounts"));
+        assert!(glob_match("*_accounts", "stg_accounts"));
+        assert!(glob_match("s?g_*s", "stg_accounts"));
+        assert!(!glob_match("stg_*", "accounts"));
+    }
+}
@@ -0,0 +1,128 @@
+//! Measures of how similar two clusters are, used to decide which clusters
+//! to merge while decomposing a graph.
+
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+/// A similarity coefficient between two sets of neighbors, computed from the
+/// number of neighbors they share and the size of each set. Coefficients are
+/// between 0 and 1, and are 0 when either set is empty. Measures are shared
+/// between threads with the `parallel` feature.
+pub trait Similarity: Sync {
+    fn coefficient(&self, shared: usize, one: usize, two: usize) -> f32;
+}
+
+/// The Jaccard index: shared neighbors over all neighbors.
+pub struct Jaccard;
+
+impl Similarity for Jaccard {
+    fn coefficient(&self, shared: usize, one: usize, two: usize) -> f32 {
+        ratio(shared, one + two - shared)
+    }
+}
+
+/// The Sørensen–Dice coefficient: twice the shared neighbors over the sum of
+/// the sizes of both sets.
+pub struct SorensenDice;
+
+impl Similarity for SorensenDice {
+    fn coefficient(&self, shared: usize, one: usize, two: usize) -> f32 {
+        ratio(2 * shared, one + two)
+    }
+}
+
+/// The overlap coefficient: shared neighbors over the size of the smaller
+/// set, so a cluster is fully similar to any cluster whose neighbors contain
+/// its own.
+pub struct Overlap;
+
+impl Similarity for Overlap {
+    fn coefficient(&self, shared: usize, one: usize, two: usize) -> f32 {
+        ratio(shared, one.min(two))
+    }
+}
+
+/// The cosine similarity of the sets as binary vectors.
+pub struct Cosine;
+
+impl Similarity for Cosine {
+    fn coefficient(&self, shared: usize, one: usize, two: usize) -> f32 {
+        if one == 0 || two == 0 {
+            return 0.0;
+        }
+        shared as f32 / ((one * two) as f32).sqrt()
+    }
+}
+
+fn ratio(numerator: usize, denominator: usize) -> f32 {
+    if denominator == 0 {
+        return 0.0;
+    }
+    numerator as f32 / denominator as f32
+}
+
+/// The built-in similarity coefficients.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SimilarityMeasure {
+    #[default]
+    Jaccard,
+    SorensenDice,
+    Overlap,
+    Cosine,
+}
+
+impl Similarity for SimilarityMeasure {
+    fn coefficient(&self, shared: usize, one: usize, two: usize) -> f32 {
+        match self {
+            SimilarityMeasure::Jaccard => Jaccard.coefficient(shared, one, two),
+            SimilarityMeasure::SorensenDice => SorensenDice.coefficient(shared, one, two),
+            SimilarityMeasure::Overlap => Overlap.coefficient(shared, one, two),
+            SimilarityMeasure::Cosine => Cosine.coefficient(shared, one, two),
+        }
+    }
+}
+
+/// Which neighbors of a cluster are compared.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NeighborDirection {
+    /// Both parents and children, ignoring the direction of edges.
+    #[default]
+    Any,
+    /// Only the nodes with an edge into the cluster, such as upstream sources.
+    Parents,
+    /// Only the nodes with an edge from the cluster, such as downstream models.
+    Children,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn coefficients() {
+        // Two sets of 2 and 8 neighbors, sharing 2.
+        assert_eq!(Jaccard.coefficient(2, 2, 8), 0.25);
+        assert_eq!(SorensenDice.coefficient(2, 2, 8), 0.4);
+        assert_eq!(Overlap.coefficient(2, 2, 8), 1.0);
+        assert_eq!(Cosine.coefficient(2, 2, 8), 0.5);
+        assert_eq!(SimilarityMeasure::Cosine.coefficient(2, 2, 8), 0.5);
+    }
+
+    #[test]
+    fn empty_sets() {
+        let measures = [
+            SimilarityMeasure::Jaccard,
+            SimilarityMeasure::SorensenDice,
+            SimilarityMeasure::Overlap,
+            SimilarityMeasure::Cosine,
+        ];
+        for measure in measures {
+            assert_eq!(measure.coefficient(0, 0, 0), 0.0);
+            assert_eq!(measure.coefficient(0, 0, 3), 0.0);
+        }
+    }
+}
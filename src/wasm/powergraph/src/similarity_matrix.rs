@@ -1,116 +1,263 @@
-use itertools::Itertools;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 
+use crate::node_set::NodeSet;
 use crate::unordered_tuple::UnorderedTuple;
-use std::collections::HashMap;
 
-// #[wasm_bindgen]
+/// The similarity of a pair of clusters, as an entry in the heap.
+#[derive(Debug, Clone, PartialEq)]
+struct Entry {
+    pair: UnorderedTuple<NodeSet>,
+    similarity: f32,
+    /// When the similarity was set, so that pairs of equal similarity are
+    /// taken most recently set first.
+    sequence: u64,
+}
+
+impl Entry {
+    fn cmp(&self, other: &Entry) -> Ordering {
+        self.similarity
+            .total_cmp(&other.similarity)
+            .then_with(|| self.sequence.cmp(&other.sequence))
+    }
+}
+
+/// The similarities between pairs of clusters, as an indexed max-heap so that
+/// the most similar pair is found, and any pair updated or removed, in
+/// logarithmic time.
 #[derive(Debug, Clone, PartialEq)]
 pub struct SimilarityMatrix {
-    matrix: Vec<(UnorderedTuple<String>, f32)>,
+    heap: Vec<Entry>,
+    /// The position of each pair in `heap`.
+    positions: HashMap<UnorderedTuple<NodeSet>, usize>,
+    /// The pairs each cluster is in, so that a cluster's pairs can be removed
+    /// without a scan of the heap.
+    pairs: BTreeMap<NodeSet, BTreeSet<UnorderedTuple<NodeSet>>>,
+    sequence: u64,
 }
 
-// #[wasm_bindgen]
 impl SimilarityMatrix {
     pub fn new() -> SimilarityMatrix {
-        let matrix = Vec::new();
-
         SimilarityMatrix {
-            matrix, // active_clusters,
+            heap: Vec::new(),
+            positions: HashMap::new(),
+            pairs: BTreeMap::new(),
+            sequence: 0,
         }
     }
 
-    pub fn remove_element(&mut self, element_id: String) {
-        // println!("Removing {:?} from the matrix.", element_id);
-
-        for index in self
-            .matrix
-            .iter()
-            .enumerate()
-            .filter_map(|(index, item)| {
-                if item.0.one == element_id || item.0.two == element_id {
-                    return Some(index);
+    /// Remove every pair containing `element`.
+    pub fn remove_element(&mut self, element: &NodeSet) {
+        for pair in self.pairs.remove(element).unwrap_or_default() {
+            let other = if pair.one == *element {
+                &pair.two
+            } else {
+                &pair.one
+            };
+            if let Some(other_pairs) = self.pairs.get_mut(other) {
+                other_pairs.remove(&pair);
+                if other_pairs.is_empty() {
+                    self.pairs.remove(other);
                 }
-                None
-            })
-            .sorted()
-            .rev()
-        {
-            self.matrix.remove(index);
+            }
+
+            if let Some(position) = self.positions.get(&pair).copied() {
+                self.remove_at(position);
+            }
         }
     }
 
-    /// Set the similarity between two clusters based on their index.
-    pub fn set_similarity(&mut self, index: UnorderedTuple<String>, similarity: f32) {
-        // self.matrix.insert(index, similarity);
-        match self.matrix.binary_search_by(|value| value.0.cmp(&index)) {
-            Ok(pos) => self.matrix[pos] = (index, similarity),
-            Err(_) => {
-                let pos = self.matrix.partition_point(|value| value.1 > similarity);
-                self.matrix.insert(pos, (index, similarity));
-            }
+    /// Set the similarity between two clusters based on their index, replacing
+    /// any similarity already set for the pair.
+    pub fn set_similarity(&mut self, index: UnorderedTuple<NodeSet>, similarity: f32) {
+        self.sequence += 1;
+
+        if let Some(&position) = self.positions.get(&index) {
+            let entry = &mut self.heap[position];
+            entry.similarity = similarity;
+            entry.sequence = self.sequence;
+            let position = self.sift_up(position);
+            self.sift_down(position);
+            return;
+        }
+
+        for element in [&index.one, &index.two] {
+            self.pairs
+                .entry(element.clone())
+                .or_default()
+                .insert(index.clone());
         }
+
+        self.positions.insert(index.clone(), self.heap.len());
+        self.heap.push(Entry {
+            pair: index,
+            similarity,
+            sequence: self.sequence,
+        });
+        self.sift_up(self.heap.len() - 1);
     }
 
     // Get the column and row with the largest similarity score
-    pub fn get_max_similarity(&self) -> Option<(UnorderedTuple<String>, f32)> {
-        if self.matrix.len() == 0 {
-            return None;
+    pub fn get_max_similarity(&self) -> Option<(UnorderedTuple<NodeSet>, f32)> {
+        self.heap
+            .first()
+            .map(|entry| (entry.pair.clone(), entry.similarity))
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    fn remove_at(&mut self, position: usize) {
+        let last = self.heap.len() - 1;
+        self.swap(position, last);
+        let entry = self.heap.pop().unwrap();
+        self.positions.remove(&entry.pair);
+
+        if position < self.heap.len() {
+            let position = self.sift_up(position);
+            self.sift_down(position);
         }
+    }
 
-        return Some(self.matrix[0].clone());
+    fn swap(&mut self, one: usize, two: usize) {
+        self.heap.swap(one, two);
+        *self.positions.get_mut(&self.heap[one].pair).unwrap() = one;
+        *self.positions.get_mut(&self.heap[two].pair).unwrap() = two;
     }
 
-    pub fn len(&self) -> usize {
-        return self.matrix.len();
+    /// Move the entry at `position` up until its parent is greater, returning
+    /// its new position.
+    fn sift_up(&mut self, mut position: usize) -> usize {
+        while position > 0 {
+            let parent = (position - 1) / 2;
+            if self.heap[position].cmp(&self.heap[parent]) != Ordering::Greater {
+                break;
+            }
+            self.swap(position, parent);
+            position = parent;
+        }
+        position
+    }
+
+    /// Move the entry at `position` down until it is greater than its children.
+    fn sift_down(&mut self, mut position: usize) {
+        loop {
+            let mut greatest = position;
+            for child in [2 * position + 1, 2 * position + 2] {
+                if child < self.heap.len()
+                    && self.heap[child].cmp(&self.heap[greatest]) == Ordering::Greater
+                {
+                    greatest = child;
+                }
+            }
+            if greatest == position {
+                return;
+            }
+            self.swap(position, greatest);
+            position = greatest;
+        }
     }
 }
 
 #[cfg(test)]
 mod test {
 
+    use crate::node_set::NodeSet;
     use crate::similarity_matrix::UnorderedTuple;
 
     use super::SimilarityMatrix;
 
+    fn pair(one: u32, two: u32) -> UnorderedTuple<NodeSet> {
+        UnorderedTuple {
+            one: NodeSet::single(one),
+            two: NodeSet::single(two),
+        }
+    }
+
     #[test]
     fn remove_element() {
+        let (foo, bar, baz, buzz) = (0, 1, 2, 3);
         let mut matrix = SimilarityMatrix::new();
 
-        matrix.set_similarity(
-            UnorderedTuple {
-                one: "foo".to_string(),
-                two: "bar".to_string(),
-            },
-            0.1,
-        );
-        matrix.set_similarity(
-            UnorderedTuple {
-                one: "foo".to_string(),
-                two: "baz".to_string(),
-            },
-            0.75,
-        );
-        matrix.set_similarity(
-            UnorderedTuple {
-                one: "foo".to_string(),
-                two: "buzz".to_string(),
-            },
-            0.7,
-        );
+        matrix.set_similarity(pair(foo, bar), 0.1);
+        matrix.set_similarity(pair(foo, baz), 0.75);
+        matrix.set_similarity(pair(foo, buzz), 0.7);
 
         assert_eq!(matrix.len(), 3);
 
-        matrix.remove_element("buzz".to_string());
+        matrix.remove_element(&NodeSet::single(buzz));
         assert_eq!(matrix.len(), 2);
-        assert_eq!(
-            matrix.get_max_similarity().unwrap(),
-            (
-                UnorderedTuple {
-                    one: "foo".to_string(),
-                    two: "baz".to_string(),
-                },
-                0.75
-            )
-        );
+        assert_eq!(matrix.get_max_similarity().unwrap(), (pair(foo, baz), 0.75));
+
+        matrix.remove_element(&NodeSet::single(foo));
+        assert_eq!(matrix.len(), 0);
+        assert_eq!(matrix.get_max_similarity(), None);
+    }
+
+    #[test]
+    fn update_similarity() {
+        let mut matrix = SimilarityMatrix::new();
+
+        matrix.set_similarity(pair(0, 1), 0.5);
+        matrix.set_similarity(pair(0, 2), 0.75);
+        matrix.set_similarity(pair(1, 2), 0.25);
+
+        // Raising a pair's similarity replaces it rather than adding the pair again.
+        matrix.set_similarity(pair(1, 0), 0.9);
+        assert_eq!(matrix.len(), 3);
+        assert_eq!(matrix.get_max_similarity().unwrap(), (pair(0, 1), 0.9));
+
+        // Lowering it lets the next most similar pair through.
+        matrix.set_similarity(pair(0, 1), 0.1);
+        assert_eq!(matrix.len(), 3);
+        assert_eq!(matrix.get_max_similarity().unwrap(), (pair(0, 2), 0.75));
+
+        matrix.remove_element(&NodeSet::single(2));
+        assert_eq!(matrix.get_max_similarity().unwrap(), (pair(0, 1), 0.1));
+    }
+
+    #[test]
+    fn ties_are_taken_most_recent_first() {
+        let mut matrix = SimilarityMatrix::new();
+
+        matrix.set_similarity(pair(0, 1), 0.5);
+        matrix.set_similarity(pair(2, 3), 0.5);
+        assert_eq!(matrix.get_max_similarity().unwrap().0, pair(2, 3));
+
+        matrix.set_similarity(pair(0, 1), 0.5);
+        assert_eq!(matrix.get_max_similarity().unwrap().0, pair(0, 1));
+    }
+
+    #[test]
+    fn matches_a_sorted_list() {
+        let mut matrix = SimilarityMatrix::new();
+        // (pair, similarity, sequence), as a sorted list would hold them.
+        let mut expected: Vec<(UnorderedTuple<NodeSet>, f32, usize)> = Vec::new();
+
+        let mut seed: u32 = 7;
+        let mut random = |bound: u32| {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            (seed >> 16) % bound
+        };
+        for sequence in 0..2000 {
+            let (one, two) = (random(12), random(12));
+            if random(5) == 0 {
+                matrix.remove_element(&NodeSet::single(one));
+                expected.retain(|(pair, _, _)| !pair.one.contains(one) && !pair.two.contains(one));
+            } else {
+                let similarity = random(8) as f32 / 8.0;
+                matrix.set_similarity(pair(one, two), similarity);
+                expected.retain(|(existing, _, _)| *existing != pair(one, two));
+                expected.push((pair(one, two), similarity, sequence));
+            }
+
+            let max = expected
+                .iter()
+                .max_by(|a, b| a.1.total_cmp(&b.1).then(a.2.cmp(&b.2)))
+                .map(|(pair, similarity, _)| (pair.clone(), *similarity));
+            assert_eq!(matrix.len(), expected.len());
+            assert_eq!(matrix.get_max_similarity(), max);
+        }
     }
 }
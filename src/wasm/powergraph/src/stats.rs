@@ -0,0 +1,217 @@
+//! Summary statistics of a decomposition, for tracking its quality over time.
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::PowerGraph;
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = Date)]
+    fn now() -> f64;
+}
+
+/// The time taken by one phase of a decomposition.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PhaseTiming {
+    pub phase: &'static str,
+    pub milliseconds: f64,
+}
+
+/// Records the time taken by each phase of a decomposition in turn. Time
+/// while the timer is paused, such as between the steps of a `Decomposition`,
+/// is not counted.
+pub(crate) struct PhaseTimer {
+    #[cfg(target_arch = "wasm32")]
+    start: f64,
+    #[cfg(not(target_arch = "wasm32"))]
+    start: std::time::Instant,
+    /// The time spent on the current phase before the timer was last paused.
+    elapsed: f64,
+    timings: Vec<PhaseTiming>,
+}
+
+impl PhaseTimer {
+    pub(crate) fn start() -> PhaseTimer {
+        PhaseTimer {
+            #[cfg(target_arch = "wasm32")]
+            start: now(),
+            #[cfg(not(target_arch = "wasm32"))]
+            start: std::time::Instant::now(),
+            elapsed: 0.0,
+            timings: Vec::new(),
+        }
+    }
+
+    /// The milliseconds since the timer was started, resumed or last lapped.
+    fn lap(&mut self) -> f64 {
+        #[cfg(target_arch = "wasm32")]
+        {
+            let end = now();
+            end - std::mem::replace(&mut self.start, end)
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let end = std::time::Instant::now();
+            end.duration_since(std::mem::replace(&mut self.start, end))
+                .as_secs_f64()
+                * 1000.0
+        }
+    }
+
+    pub(crate) fn pause(&mut self) {
+        self.elapsed += self.lap();
+    }
+
+    pub(crate) fn resume(&mut self) {
+        self.lap();
+    }
+
+    /// Record the time since the previous phase finished as `phase`.
+    pub(crate) fn finish(&mut self, phase: &'static str) {
+        let milliseconds = std::mem::take(&mut self.elapsed) + self.lap();
+
+        self.timings.push(PhaseTiming {
+            phase,
+            milliseconds,
+        });
+    }
+
+    pub(crate) fn timings(&self) -> &[PhaseTiming] {
+        &self.timings
+    }
+}
+
+/// Summary statistics of a decomposed power graph.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DecompositionStats {
+    pub nodes: usize,
+    /// The number of edges in the graph that was decomposed.
+    pub original_edges: usize,
+    /// The number of power edges found, excluding residual edges.
+    pub power_edges: usize,
+    /// The number of plain edges left over after decomposition.
+    pub residual_edges: usize,
+    /// The fraction of the original edges saved by drawing power edges and
+    /// residual edges instead.
+    pub edge_reduction: f64,
+    pub power_nodes: usize,
+    /// The number of power nodes with each number of members.
+    pub power_node_sizes: BTreeMap<usize, usize>,
+    /// The largest number of power nodes containing a power node.
+    pub max_depth: usize,
+    /// The time taken by each phase of the last decomposition.
+    pub timings: Vec<PhaseTiming>,
+}
+
+#[wasm_bindgen]
+impl PowerGraph {
+    /// Summarize the decomposition, as with `stats`, returning the statistics
+    /// as a plain object.
+    #[wasm_bindgen(js_name = stats)]
+    pub fn stats_to_object(&self) -> JsValue {
+        self.stats()
+            .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+            .unwrap()
+    }
+}
+
+impl PowerGraph {
+    pub fn stats(&self) -> DecompositionStats {
+        let original_edges = self.edges.len();
+        let residual_edges = self
+            .power_edges
+            .iter()
+            .filter(|power_edge| power_edge.residual)
+            .count();
+        let power_edges = self.power_edges.len() - residual_edges;
+
+        let edge_reduction = if original_edges == 0 {
+            0.0
+        } else {
+            1.0 - (power_edges + residual_edges) as f64 / original_edges as f64
+        };
+
+        let mut power_node_sizes = BTreeMap::new();
+        for power_node in self.power_nodes.iter() {
+            *power_node_sizes
+                .entry(power_node.cluster.size())
+                .or_default() += 1;
+        }
+
+        DecompositionStats {
+            nodes: self.nodes.len(),
+            original_edges,
+            power_edges,
+            residual_edges,
+            edge_reduction,
+            power_nodes: self.power_nodes.len(),
+            power_node_sizes,
+            max_depth: self
+                .power_nodes
+                .iter()
+                .map(|power_node| power_node.depth)
+                .max()
+                .unwrap_or(0),
+            timings: self.timings.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crate::{Edge, Node, PowerGraph};
+
+    #[test]
+    fn decomposition_stats() {
+        let nodes = ["s1", "s2", "m1", "m2", "m3", "x"]
+            .into_iter()
+            .map(|id| Node::new(id.to_string(), String::new()))
+            .collect();
+        let edges = vec![
+            Edge::new("s1", "m1"),
+            Edge::new("s1", "m2"),
+            Edge::new("s1", "m3"),
+            Edge::new("s2", "m1"),
+            Edge::new("s2", "m2"),
+            Edge::new("s2", "m3"),
+            Edge::new("m1", "x"),
+        ];
+        let mut powergraph = PowerGraph::new(nodes, edges);
+        powergraph.decompose();
+
+        let stats = powergraph.stats();
+
+        // `s1-s2` to `m1-m2-m3` is a power edge, leaving `m1 -> x` residual.
+        assert_eq!(stats.nodes, 6);
+        assert_eq!(stats.original_edges, 7);
+        assert_eq!(stats.power_edges, 1);
+        assert_eq!(stats.residual_edges, 1);
+        assert_eq!(stats.edge_reduction, 1.0 - 2.0 / 7.0);
+        assert_eq!(stats.power_nodes, 8);
+        assert_eq!(
+            stats.power_node_sizes,
+            BTreeMap::from([(1, 6), (2, 1), (3, 1)])
+        );
+        assert_eq!(stats.max_depth, 1);
+
+        let phases: Vec<&str> = stats.timings.iter().map(|timing| timing.phase).collect();
+        assert_eq!(
+            phases,
+            vec![
+                "singletons",
+                "similarity",
+                "neighborhoods",
+                "candidates",
+                "power_edges",
+                "residual_edges",
+                "aggregation"
+            ]
+        );
+    }
+}
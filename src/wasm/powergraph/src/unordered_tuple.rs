@@ -1,6 +1,4 @@
-use std::{cmp::Ordering, fmt::Display};
-
-use itertools::Itertools;
+use std::cmp::Ordering;
 
 #[derive(Debug, Clone)]
 pub struct UnorderedTuple<T> {
@@ -8,33 +6,36 @@ pub struct UnorderedTuple<T> {
     pub two: T,
 }
 
-impl<T: Ord + Display> PartialOrd for UnorderedTuple<T> {
+impl<T: Ord> PartialOrd for UnorderedTuple<T> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.cmp(other))
     }
 }
 
-impl<T: Ord + Display> Ord for UnorderedTuple<T> {
+/// Tuples are compared by their items in order, so that a tuple is equal to
+/// its reverse, as with `PartialEq`.
+impl<T: Ord> Ord for UnorderedTuple<T> {
     fn cmp(&self, other: &Self) -> Ordering {
-        let items: Vec<&T> = vec![&self.one, &self.two];
-        let other_items: Vec<&T> = vec![&other.one, &other.two];
-        items
-            .into_iter()
-            .join("-")
-            .cmp(&other_items.iter().join("-"))
+        self.sorted().cmp(&other.sorted())
     }
 }
 
-impl<T: std::hash::Hash + Ord + Clone> std::hash::Hash for UnorderedTuple<T> {
+impl<T: Ord> UnorderedTuple<T> {
+    fn sorted(&self) -> (&T, &T) {
+        if self.one <= self.two {
+            (&self.one, &self.two)
+        } else {
+            (&self.two, &self.one)
+        }
+    }
+}
+
+impl<T: std::hash::Hash + Ord> std::hash::Hash for UnorderedTuple<T> {
     fn hash<H>(&self, state: &mut H)
     where
         H: std::hash::Hasher,
     {
-        let items: Vec<&T> = vec![&self.one, &self.two];
-
-        for item in items.iter().sorted() {
-            item.hash(state);
-        }
+        self.sorted().hash(state);
     }
 }
 
@@ -0,0 +1,320 @@
+//! Checks that a decomposed power graph still represents its input graph.
+
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::{interner::NodeIndex, Edge, PowerEdge, PowerGraph, PowerNode};
+
+/// Two power edges that imply some of the same edges.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PowerEdgeOverlap {
+    pub one: PowerEdge,
+    pub two: PowerEdge,
+    /// The edges implied by both power edges.
+    pub edges: Vec<Edge>,
+}
+
+/// The differences between the edges implied by a power graph and the edges
+/// of the graph it was decomposed from.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct VerificationReport {
+    /// Edges of the graph that no power edge implies.
+    pub missing_edges: Vec<Edge>,
+    /// Edges implied by a power edge that are not in the graph.
+    pub spurious_edges: Vec<Edge>,
+    /// Power edges that imply the same edge more than once.
+    pub overlapping_power_edges: Vec<PowerEdgeOverlap>,
+    /// Pairs of power nodes that share members without either containing
+    /// the other, so that power nodes do not form a containment tree.
+    pub crossing_power_nodes: Vec<(String, String)>,
+}
+
+impl VerificationReport {
+    /// Whether the power graph represents the graph exactly, with each edge
+    /// implied by a single power edge.
+    pub fn is_valid(&self) -> bool {
+        self.missing_edges.is_empty()
+            && self.spurious_edges.is_empty()
+            && self.overlapping_power_edges.is_empty()
+            && self.crossing_power_nodes.is_empty()
+    }
+}
+
+/// A key identifying an edge, ignoring its direction unless `directed`.
+fn edge_key(edge: &Edge, directed: bool) -> (String, String) {
+    if directed || edge.from <= edge.to {
+        (edge.from.clone(), edge.to.clone())
+    } else {
+        (edge.to.clone(), edge.from.clone())
+    }
+}
+
+/// The edge of the graph that `edge` stands for: itself or, unless
+/// `directed`, the edge between its nodes in the other direction. Edges in
+/// both directions between two nodes are each their own edge of the graph.
+fn graph_edge(
+    edge: &Edge,
+    graph_edges: &HashSet<(String, String)>,
+    directed: bool,
+) -> Option<(String, String)> {
+    let forward = (edge.from.clone(), edge.to.clone());
+    let backward = (edge.to.clone(), edge.from.clone());
+    if graph_edges.contains(&forward) {
+        Some(forward)
+    } else if !directed && graph_edges.contains(&backward) {
+        Some(backward)
+    } else {
+        None
+    }
+}
+
+fn sort_edges(edges: &mut [Edge]) {
+    edges.sort_by(|a, b| (&a.from, &a.to).cmp(&(&b.from, &b.to)));
+}
+
+#[wasm_bindgen]
+impl PowerGraph {
+    /// Verify the decomposition, as with `verify`, returning the report as a
+    /// plain object.
+    #[wasm_bindgen(js_name = verify)]
+    pub fn verify_to_object(&self) -> JsValue {
+        self.verify()
+            .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+            .unwrap()
+    }
+}
+
+impl PowerGraph {
+    /// Expand every power edge into the edges between the members of its power
+    /// nodes and compare them with the edges of the graph. Edges are compared
+    /// by direction only if the decomposition was directed.
+    pub fn verify(&self) -> VerificationReport {
+        let graph_edges: HashSet<(String, String)> = self
+            .edges
+            .clone()
+            .into_iter()
+            .map(|edge| (edge.from, edge.to))
+            .collect();
+
+        // The power edges implying each edge, by index, keyed by the edge of
+        // the graph they stand for.
+        let mut implied: BTreeMap<(String, String), BTreeSet<usize>> = BTreeMap::new();
+        let mut spurious_edges: Vec<Edge> = Vec::new();
+        for (index, power_edge) in self.power_edges.iter().enumerate() {
+            for edge in self.implied_edges(power_edge) {
+                let key = match graph_edge(&edge, &graph_edges, self.directed) {
+                    Some(key) => key,
+                    None => {
+                        let key = edge_key(&edge, self.directed);
+                        spurious_edges.push(edge);
+                        key
+                    }
+                };
+                implied.entry(key).or_default().insert(index);
+            }
+        }
+        sort_edges(&mut spurious_edges);
+        spurious_edges.dedup();
+
+        let mut missing_edges: Vec<Edge> = self
+            .edges
+            .clone()
+            .into_iter()
+            .filter(|edge| !implied.contains_key(&(edge.from.clone(), edge.to.clone())))
+            .collect();
+        sort_edges(&mut missing_edges);
+
+        let mut overlaps: BTreeMap<(usize, usize), Vec<Edge>> = BTreeMap::new();
+        for ((from, to), power_edges) in implied.iter() {
+            let power_edges: Vec<usize> = power_edges.iter().copied().collect();
+            for (position, &one) in power_edges.iter().enumerate() {
+                for &two in &power_edges[position + 1..] {
+                    overlaps
+                        .entry((one, two))
+                        .or_default()
+                        .push(Edge::new(from, to));
+                }
+            }
+        }
+        let overlapping_power_edges = overlaps
+            .into_iter()
+            .map(|((one, two), edges)| PowerEdgeOverlap {
+                one: self.power_edges[one].clone(),
+                two: self.power_edges[two].clone(),
+                edges,
+            })
+            .collect();
+
+        VerificationReport {
+            missing_edges,
+            spurious_edges,
+            overlapping_power_edges,
+            crossing_power_nodes: self.crossing_power_nodes(),
+        }
+    }
+
+    /// Pairs of power nodes that are neither nested nor disjoint. Power nodes
+    /// are laminar if the power nodes containing each node, largest first, are
+    /// each contained in the one before, so only those neighbors are compared.
+    fn crossing_power_nodes(&self) -> Vec<(String, String)> {
+        let mut containing: BTreeMap<NodeIndex, Vec<&PowerNode>> = BTreeMap::new();
+        for power_node in self.power_nodes.iter() {
+            for item in power_node.cluster.items.iter() {
+                containing.entry(item).or_default().push(power_node);
+            }
+        }
+
+        let mut crossing: BTreeSet<(String, String)> = BTreeSet::new();
+        for power_nodes in containing.values_mut() {
+            power_nodes.sort_by(|a, b| {
+                b.cluster
+                    .size()
+                    .cmp(&a.cluster.size())
+                    .then_with(|| a.id.cmp(&b.id))
+            });
+            for pair in power_nodes.windows(2) {
+                if !pair[1].cluster.items.is_subset_of(&pair[0].cluster.items) {
+                    crossing.insert((pair[0].id.clone(), pair[1].id.clone()));
+                }
+            }
+        }
+
+        crossing.into_iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::tests::{power_edge, power_node};
+    use crate::{DecompositionOptions, Node};
+
+    fn powergraph(edges: &[(&str, &str)]) -> PowerGraph {
+        let nodes = edges
+            .iter()
+            .flat_map(|(from, to)| [*from, *to])
+            .collect::<BTreeSet<&str>>()
+            .into_iter()
+            .map(|id| Node::new(id.to_string(), String::new()))
+            .collect();
+        let edges = edges.iter().map(|(from, to)| Edge::new(from, to)).collect();
+        PowerGraph::new(nodes, edges)
+    }
+
+    #[test]
+    fn verify_decomposition() {
+        let mut powergraph = powergraph(&[
+            ("s1", "m1"),
+            ("s1", "m2"),
+            ("s2", "m1"),
+            ("s2", "m2"),
+            ("m1", "x"),
+            ("m2", "x"),
+        ]);
+        powergraph
+            .decompose_with_options(&DecompositionOptions {
+                directed: true,
+                ..Default::default()
+            })
+            .unwrap();
+
+        let report = powergraph.verify();
+        assert!(report.missing_edges.is_empty());
+        assert!(report.spurious_edges.is_empty());
+    }
+
+    #[test]
+    fn verify_mismatches() {
+        // Undirected, edges in both directions between two nodes are distinct
+        // edges, so residual edges for each do not overlap.
+        let mut both_ways = powergraph(&[("a", "b"), ("b", "a")]);
+        both_ways.power_edges = vec![power_edge("a", "b", true), power_edge("b", "a", true)];
+        both_ways.set_power_nodes(vec![
+            power_node(&both_ways, &["a"]),
+            power_node(&both_ways, &["b"]),
+        ]);
+        assert!(both_ways.verify().is_valid());
+
+        // Without the edge from `b`, both stand for the edge from `a`.
+        let mut one_way = powergraph(&[("a", "b")]);
+        one_way.power_edges = both_ways.power_edges.clone();
+        one_way.set_power_nodes(vec![
+            power_node(&one_way, &["a"]),
+            power_node(&one_way, &["b"]),
+        ]);
+        let report = one_way.verify();
+        assert!(report.missing_edges.is_empty());
+        assert!(report.spurious_edges.is_empty());
+        assert_eq!(
+            report.overlapping_power_edges,
+            vec![PowerEdgeOverlap {
+                one: one_way.power_edges[0].clone(),
+                two: one_way.power_edges[1].clone(),
+                edges: vec![Edge::new("a", "b")],
+            }]
+        );
+
+        let mut powergraph = powergraph(&[("a", "b"), ("a", "c"), ("b", "c")]);
+        powergraph.power_edges = vec![
+            power_edge("a", "b-c", false),
+            power_edge("a", "b", true),
+            power_edge("c", "a", true),
+        ];
+        powergraph.set_power_nodes(vec![
+            power_node(&powergraph, &["a"]),
+            power_node(&powergraph, &["b"]),
+            power_node(&powergraph, &["c"]),
+            power_node(&powergraph, &["b", "c"]),
+        ]);
+
+        let report = powergraph.verify();
+
+        assert!(!report.is_valid());
+        assert_eq!(report.missing_edges, vec![Edge::new("b", "c")]);
+        assert!(report.spurious_edges.is_empty());
+        assert_eq!(
+            report.overlapping_power_edges,
+            vec![
+                PowerEdgeOverlap {
+                    one: powergraph.power_edges[0].clone(),
+                    two: powergraph.power_edges[1].clone(),
+                    edges: vec![Edge::new("a", "b")],
+                },
+                PowerEdgeOverlap {
+                    one: powergraph.power_edges[0].clone(),
+                    two: powergraph.power_edges[2].clone(),
+                    edges: vec![Edge::new("a", "c")],
+                },
+            ]
+        );
+
+        // Directed, the edge from `c` is spurious and `a -> c` is not missing.
+        powergraph.directed = true;
+        let report = powergraph.verify();
+        assert_eq!(report.missing_edges, vec![Edge::new("b", "c")]);
+        assert_eq!(report.spurious_edges, vec![Edge::new("c", "a")]);
+        assert_eq!(report.overlapping_power_edges.len(), 1);
+    }
+
+    #[test]
+    fn verify_crossing_power_nodes() {
+        let mut powergraph = powergraph(&[("a", "b"), ("b", "c")]);
+        powergraph.set_power_nodes(vec![
+            power_node(&powergraph, &["a"]),
+            power_node(&powergraph, &["b"]),
+            power_node(&powergraph, &["c"]),
+            power_node(&powergraph, &["a", "b"]),
+            power_node(&powergraph, &["b", "c"]),
+        ]);
+
+        assert_eq!(
+            powergraph.verify().crossing_power_nodes,
+            vec![("a-b".to_string(), "b-c".to_string())]
+        );
+
+        powergraph.power_nodes.pop();
+        assert!(powergraph.verify().crossing_power_nodes.is_empty());
+    }
+}